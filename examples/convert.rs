@@ -1,6 +1,6 @@
 use std::env;
-use std::fs;
-use std::io::{self, Read};
+use std::fs::File;
+use std::io;
 use std::process;
 use std::str::FromStr;
 
@@ -18,25 +18,11 @@ fn run() -> Result<(), Box<dyn std::error::Error>> {
     })?;
     let target = ustx::Version::from_str(&target_raw)?;
 
-    let input = if let Some(path) = args.next() {
-        if path == "-" {
-            read_stdin()?
-        } else {
-            fs::read_to_string(path)?
-        }
-    } else {
-        read_stdin()?
+    let mut project = match args.next() {
+        Some(path) if path != "-" => ustx::Project::from_reader(File::open(path)?)?,
+        _ => ustx::Project::from_reader(io::stdin().lock())?,
     };
-
-    let mut project = ustx::Project::from_yaml_str(&input)?;
     project.convert_to(target)?;
-    let output = project.to_yaml_string()?;
-    print!("{output}");
+    project.to_writer(io::stdout().lock())?;
     Ok(())
 }
-
-fn read_stdin() -> io::Result<String> {
-    let mut buffer = String::new();
-    io::stdin().read_to_string(&mut buffer)?;
-    Ok(buffer)
-}
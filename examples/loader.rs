@@ -1,6 +1,6 @@
 use std::env;
-use std::fs;
-use std::io::{self, Read};
+use std::fs::File;
+use std::io;
 use std::process;
 
 fn main() {
@@ -12,23 +12,11 @@ fn main() {
 
 fn run() -> Result<(), Box<dyn std::error::Error>> {
     let mut args = env::args().skip(1);
-    let input = if let Some(path) = args.next() {
-        if path == "-" {
-            read_stdin()?
-        } else {
-            fs::read_to_string(path)?
-        }
-    } else {
-        read_stdin()?
+    let project = match args.next() {
+        Some(path) if path != "-" => ustx::Project::from_reader(File::open(path)?)?,
+        _ => ustx::Project::from_reader(io::stdin().lock())?,
     };
 
-    let project = ustx::Project::from_yaml_str(&input)?;
-    println!("{project:#?}");
+    println!("{}", project.summary_line());
     Ok(())
 }
-
-fn read_stdin() -> io::Result<String> {
-    let mut buffer = String::new();
-    io::stdin().read_to_string(&mut buffer)?;
-    Ok(buffer)
-}
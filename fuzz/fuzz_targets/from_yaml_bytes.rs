@@ -0,0 +1,12 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // Parsing must only ever fail with an error, and anything that parses must serialize.
+    if let Ok(project) = ustx::Project::from_yaml_bytes(data) {
+        project
+            .to_yaml_string()
+            .expect("a parsed project must serialize");
+    }
+});
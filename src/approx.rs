@@ -0,0 +1,211 @@
+use std::collections::BTreeMap;
+
+use crate::expression::{Curve, Expression, ExpressionDescriptor, ExpressionType};
+use crate::note::{Note, Pitch, PitchPoint, PitchPointShape, Vibrato};
+use crate::part::{VoicePart, WavePart};
+use crate::phoneme::PhonemeOverride;
+use crate::project::Project;
+use crate::time::{Tempo, TimeSignature};
+use crate::track::{RenderSettings, Track};
+use crate::version::Version;
+
+impl Project {
+    /// Returns whether two projects are equal, allowing every floating-point field to
+    /// differ by up to `epsilon`.
+    ///
+    /// Every other field must match exactly, as with `==`. This tolerates the drift a
+    /// float can pick up from a round trip through text, such as `120.0` coming back as
+    /// `119.99999`. Two `NaN`s compare equal.
+    #[must_use]
+    pub fn approx_eq(&self, other: &Self, epsilon: f32) -> bool {
+        ApproxEq::approx_eq(self, other, f64::from(epsilon))
+    }
+}
+
+/// Returns whether `a` and `b` differ by at most `epsilon`, treating two `NaN`s as equal.
+#[inline]
+pub fn approx_eq(a: f64, b: f64, epsilon: f64) -> bool {
+    a == b || (a - b).abs() <= epsilon || (a.is_nan() && b.is_nan())
+}
+
+/// Equality that tolerates a bounded difference in floating-point values.
+trait ApproxEq {
+    fn approx_eq(&self, other: &Self, epsilon: f64) -> bool;
+}
+
+impl ApproxEq for f64 {
+    #[inline]
+    fn approx_eq(&self, other: &Self, epsilon: f64) -> bool {
+        approx_eq(*self, *other, epsilon)
+    }
+}
+
+impl ApproxEq for f32 {
+    #[inline]
+    fn approx_eq(&self, other: &Self, epsilon: f64) -> bool {
+        approx_eq(f64::from(*self), f64::from(*other), epsilon)
+    }
+}
+
+impl<T: ApproxEq> ApproxEq for Option<T> {
+    #[inline]
+    fn approx_eq(&self, other: &Self, epsilon: f64) -> bool {
+        match (self, other) {
+            (Some(a), Some(b)) => a.approx_eq(b, epsilon),
+            (a, b) => a.is_none() && b.is_none(),
+        }
+    }
+}
+
+impl<T: ApproxEq> ApproxEq for Vec<T> {
+    #[inline]
+    fn approx_eq(&self, other: &Self, epsilon: f64) -> bool {
+        self.len() == other.len() && self.iter().zip(other).all(|(a, b)| a.approx_eq(b, epsilon))
+    }
+}
+
+impl<K: Ord, V: ApproxEq> ApproxEq for BTreeMap<K, V> {
+    #[inline]
+    fn approx_eq(&self, other: &Self, epsilon: f64) -> bool {
+        self.len() == other.len()
+            && self
+                .iter()
+                .zip(other)
+                .all(|((ka, va), (kb, vb))| ka == kb && va.approx_eq(vb, epsilon))
+    }
+}
+
+/// Implements `ApproxEq` as plain equality for types without floating-point data.
+macro_rules! exact {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl ApproxEq for $ty {
+                #[inline]
+                fn approx_eq(&self, other: &Self, _epsilon: f64) -> bool {
+                    self == other
+                }
+            }
+        )*
+    };
+}
+
+/// Implements `ApproxEq` for a struct by comparing each listed field.
+macro_rules! fields {
+    ($ty:ty { $($field:ident),* $(,)? }) => {
+        impl ApproxEq for $ty {
+            #[inline]
+            fn approx_eq(&self, other: &Self, epsilon: f64) -> bool {
+                let Self { $($field),* } = self;
+                $($field.approx_eq(&other.$field, epsilon))&&*
+            }
+        }
+    };
+}
+
+exact!(
+    bool,
+    i32,
+    String,
+    Version,
+    ExpressionType,
+    PitchPointShape,
+    Curve,
+    TimeSignature,
+    RenderSettings,
+);
+
+fields!(Project {
+    name,
+    comment,
+    output_dir,
+    cache_dir,
+    ustx_version,
+    resolution,
+    bpm,
+    beat_per_bar,
+    beat_unit,
+    expressions,
+    exp_selectors,
+    exp_primary,
+    exp_secondary,
+    key,
+    time_signatures,
+    tempos,
+    tracks,
+    voice_parts,
+    wave_parts,
+});
+fields!(ExpressionDescriptor {
+    name,
+    abbr,
+    r#type,
+    min,
+    max,
+    default_value,
+    is_flag,
+    flag,
+    options,
+});
+fields!(Expression { index, abbr, value });
+fields!(Tempo { position, bpm });
+fields!(Track {
+    singer,
+    phonemizer,
+    renderer_settings,
+    track_name,
+    track_color,
+    mute,
+    solo,
+    volume,
+    pan,
+    track_expressions,
+    voice_color_names,
+});
+fields!(VoicePart {
+    name,
+    comment,
+    track_no,
+    position,
+    notes,
+    curves,
+});
+fields!(WavePart {
+    name,
+    comment,
+    track_no,
+    position,
+    relative_path,
+    file_duration_ms,
+    skip_ms,
+    trim_ms,
+});
+fields!(Note {
+    position,
+    duration,
+    tone,
+    lyric,
+    pitch,
+    vibrato,
+    phoneme_expressions,
+    phoneme_overrides,
+    phoneme_indexes,
+});
+fields!(Pitch { data, snap_first });
+fields!(PitchPoint { x, y, shape });
+fields!(Vibrato {
+    length,
+    period,
+    depth,
+    r#in,
+    out,
+    shift,
+    drift,
+    vol_link,
+});
+fields!(PhonemeOverride {
+    index,
+    phoneme,
+    offset,
+    preutter_delta,
+    overlap_delta,
+});
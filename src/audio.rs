@@ -0,0 +1,120 @@
+use hound::{SampleFormat, WavReader};
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use crate::error::Error;
+use crate::part::WavePart;
+
+impl WavePart {
+    /// Computes waveform peaks for drawing the part on a timeline.
+    ///
+    /// The audio file is resolved against `project_dir` and must be a WAV file. The
+    /// played range, from `skip_ms` to `file_duration_ms - trim_ms`, is divided into
+    /// `buckets` equal spans, and the minimum and maximum sample of each span across all
+    /// channels is returned, normalized to `-1.0..=1.0`. Spans without samples, including
+    /// those past the end of a file shorter than its header claims, yield `(0.0, 0.0)`.
+    /// The samples are read in a single pass without being buffered.
+    ///
+    /// Missing or unreadable files and non-WAV audio are reported as [`Error::Audio`].
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn compute_peaks(
+        &self,
+        project_dir: &Path,
+        buckets: usize,
+    ) -> Result<Vec<(f32, f32)>, Error> {
+        let mut reader = self.open_wav(project_dir)?;
+        let spec = reader.spec();
+        let total_frames = u64::from(reader.duration());
+        let ms_to_frame = |ms: f64| {
+            ((ms.max(0.0) * f64::from(spec.sample_rate) / 1000.0).round() as u64).min(total_frames)
+        };
+        let start = ms_to_frame(self.skip_ms);
+        let end = ms_to_frame(self.file_duration_ms - self.trim_ms).max(start);
+        reader
+            .seek(u32::try_from(start).unwrap_or(u32::MAX))
+            .map_err(|err| Error::Audio(hound::Error::IoError(err)))?;
+
+        let channels = u64::from(spec.channels.max(1));
+        let frame_count = end - start;
+        let bucket_count = u64::try_from(buckets).unwrap_or(u64::MAX);
+        // The frame at which `bucket` ends, computed in u128 so it cannot overflow.
+        let bucket_end = |bucket: usize| {
+            let bucket = u128::try_from(bucket).unwrap_or(u128::MAX);
+            let end = bucket.saturating_add(1) * u128::from(frame_count)
+                / u128::from(bucket_count.max(1));
+            u64::try_from(end).unwrap_or(u64::MAX)
+        };
+
+        let mut peaks = vec![None; buckets];
+        let mut bucket = 0;
+        let mut end_of_bucket = bucket_end(bucket);
+        for_each_normalized(
+            &mut reader,
+            frame_count.saturating_mul(channels),
+            |index, sample| {
+                let frame = index / channels;
+                while frame >= end_of_bucket && bucket < buckets {
+                    bucket += 1;
+                    end_of_bucket = bucket_end(bucket);
+                }
+                if let Some(peak) = peaks.get_mut(bucket) {
+                    *peak = Some(peak.map_or((sample, sample), |(min, max): (f32, f32)| {
+                        (min.min(sample), max.max(sample))
+                    }));
+                }
+            },
+        )?;
+        Ok(peaks
+            .into_iter()
+            .map(|peak| peak.unwrap_or((0.0, 0.0)))
+            .collect())
+    }
+
+    /// Sets `file_duration_ms` to the length of the audio file, resolved against
+    /// `project_dir`.
+    ///
+    /// The file must be a WAV file; missing or unreadable files and non-WAV audio are
+    /// reported as [`Error::Audio`], leaving the part unchanged.
+    pub fn refresh_duration(&mut self, project_dir: &Path) -> Result<(), Error> {
+        let reader = self.open_wav(project_dir)?;
+        let sample_rate = reader.spec().sample_rate;
+        self.file_duration_ms = if sample_rate == 0 {
+            0.0
+        } else {
+            f64::from(reader.duration()) * 1000.0 / f64::from(sample_rate)
+        };
+        Ok(())
+    }
+
+    /// Opens the part's audio file, resolved against `project_dir`, as a WAV file.
+    fn open_wav(&self, project_dir: &Path) -> Result<WavReader<BufReader<File>>, Error> {
+        Ok(WavReader::open(self.resolve_path(project_dir))?)
+    }
+}
+
+/// Calls `f` with the index and value, scaled to `-1.0..=1.0`, of each of the next
+/// `limit` samples of `reader`.
+#[allow(clippy::cast_precision_loss)]
+fn for_each_normalized<R: Read, F: FnMut(u64, f32)>(
+    reader: &mut WavReader<R>,
+    limit: u64,
+    mut f: F,
+) -> Result<(), Error> {
+    let spec = reader.spec();
+    let limit = usize::try_from(limit).unwrap_or(usize::MAX);
+    match spec.sample_format {
+        SampleFormat::Float => {
+            for (index, sample) in (0..).zip(reader.samples::<f32>().take(limit)) {
+                f(index, sample?);
+            }
+        }
+        SampleFormat::Int => {
+            let scale = 2_f32.powi(i32::from(spec.bits_per_sample) - 1);
+            for (index, sample) in (0..).zip(reader.samples::<i32>().take(limit)) {
+                f(index, sample? as f32 / scale);
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,85 @@
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{BufWriter, IntoInnerError};
+use std::path::{Path, PathBuf};
+
+use crate::error::Error;
+use crate::project::Project;
+use crate::version::Version;
+
+/// Represents the result of converting a single file with [`convert_dir`].
+#[derive(Debug)]
+pub enum ConvertOutcome {
+    /// The project was already at the target version, so the file was left untouched.
+    Unchanged,
+    /// The project was converted and the file was rewritten.
+    Converted,
+    /// The file could not be read, converted or written.
+    Failed(Error),
+}
+
+/// Converts every `.ustx` file directly inside `dir` to the `target` version.
+///
+/// Each file is loaded, passed through [`Project::convert_to`] and written back only if
+/// the conversion changed its content. Subdirectories are not visited. Outcomes are
+/// returned per file in path order, and a failure on one file does not stop the others.
+///
+/// Returns an error only if `dir` itself cannot be read.
+pub fn convert_dir(dir: &Path, target: Version) -> Result<Vec<(PathBuf, ConvertOutcome)>, Error> {
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let is_ustx = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("ustx"));
+        if is_ustx && path.is_file() {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    Ok(paths
+        .into_iter()
+        .map(|path| {
+            let outcome = convert_file(&path, target).unwrap_or_else(ConvertOutcome::Failed);
+            (path, outcome)
+        })
+        .collect())
+}
+
+fn convert_file(path: &Path, target: Version) -> Result<ConvertOutcome, Error> {
+    let mut project = Project::from_reader(File::open(path)?)?;
+    let before = project.content_hash();
+    project.convert_to(target)?;
+    if project.content_hash() == before {
+        return Ok(ConvertOutcome::Unchanged);
+    }
+    write_replacing(path, &project)?;
+    Ok(ConvertOutcome::Converted)
+}
+
+/// Writes `project` to a temporary file next to `path` and renames it over `path`, so
+/// that the original file is left intact if writing fails partway.
+fn write_replacing(path: &Path, project: &Project) -> Result<(), Error> {
+    let mut temp_name = OsString::from(".");
+    temp_name.push(path.file_name().unwrap_or_default());
+    temp_name.push(".tmp");
+    let temp_path = path.with_file_name(temp_name);
+
+    let result = (|| {
+        let mut writer = BufWriter::new(File::create(&temp_path)?);
+        project.to_writer(&mut writer)?;
+        writer
+            .into_inner()
+            .map_err(IntoInnerError::into_error)?
+            .sync_all()?;
+        fs::rename(&temp_path, path)?;
+        Ok(())
+    })();
+    if result.is_err() {
+        // The write already failed; a leftover temporary file is the lesser problem, so a
+        // failure to remove it is not reported over the original error.
+        fs::remove_file(&temp_path).ok();
+    }
+    result
+}
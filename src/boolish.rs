@@ -0,0 +1,35 @@
+use serde::de::{Error as _, Unexpected};
+use serde::{Deserialize, Deserializer};
+
+/// Deserializes a boolean given either natively, as `0` or `1`, or as one of the strings
+/// some third-party exporters write: `"true"`, `"false"`, `"True"`, `"False"`, `"0"`, and
+/// `"1"`.
+///
+/// The field is always serialized back as a native boolean.
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Boolish {
+        Bool(bool),
+        Number(i64),
+        Text(String),
+    }
+
+    match Boolish::deserialize(deserializer)? {
+        Boolish::Bool(value) => Ok(value),
+        Boolish::Number(0) => Ok(false),
+        Boolish::Number(1) => Ok(true),
+        Boolish::Number(number) => Err(D::Error::invalid_value(
+            Unexpected::Signed(number),
+            &"a boolean",
+        )),
+        Boolish::Text(text) => match text.as_str() {
+            "true" | "True" | "1" => Ok(true),
+            "false" | "False" | "0" => Ok(false),
+            _ => Err(D::Error::invalid_value(
+                Unexpected::Str(&text),
+                &"a boolean",
+            )),
+        },
+    }
+}
@@ -0,0 +1,167 @@
+use std::fmt::{self, Display, Formatter};
+
+use crate::project::Project;
+
+/// Represents a single category of change between two projects.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ProjectChange {
+    /// A top-level project field changed.
+    FieldChanged(&'static str),
+    /// A track was added at the given index.
+    TrackAdded(usize),
+    /// A track was removed from the given index.
+    TrackRemoved(usize),
+    /// A voice part was added at the given index.
+    VoicePartAdded(usize),
+    /// A voice part was removed from the given index.
+    VoicePartRemoved(usize),
+    /// A wave part was added at the given index.
+    WavePartAdded(usize),
+    /// A wave part was removed from the given index.
+    WavePartRemoved(usize),
+    /// The number of notes in a voice part changed.
+    NoteCountChanged {
+        /// The index of the voice part.
+        part_index: usize,
+        /// The number of notes before the change.
+        before: usize,
+        /// The number of notes after the change.
+        after: usize,
+    },
+}
+
+impl Display for ProjectChange {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::FieldChanged(field) => write!(f, "changed {field}"),
+            Self::TrackAdded(index) => write!(f, "added track {index}"),
+            Self::TrackRemoved(index) => write!(f, "removed track {index}"),
+            Self::VoicePartAdded(index) => write!(f, "added voice part {index}"),
+            Self::VoicePartRemoved(index) => write!(f, "removed voice part {index}"),
+            Self::WavePartAdded(index) => write!(f, "added wave part {index}"),
+            Self::WavePartRemoved(index) => write!(f, "removed wave part {index}"),
+            Self::NoteCountChanged {
+                part_index,
+                before,
+                after,
+            } => write!(f, "voice part {part_index}: {before} -> {after} notes"),
+        }
+    }
+}
+
+/// Represents a summary of the differences between two projects.
+///
+/// This is not a full structural diff: it lists which top-level fields changed, which
+/// tracks and parts were added or removed, and how note counts changed per voice part.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProjectDiff {
+    /// The changes, in project field order.
+    pub changes: Vec<ProjectChange>,
+}
+
+impl ProjectDiff {
+    /// Returns whether no changes were detected.
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+impl Display for ProjectDiff {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.changes.is_empty() {
+            return f.write_str("no changes");
+        }
+        for (index, change) in self.changes.iter().enumerate() {
+            if index > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{change}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Project {
+    /// Summarizes the differences between `self` and `other`.
+    ///
+    /// Tracks and parts are compared by position, so an element inserted in the middle
+    /// is reported as an addition at the end.
+    #[must_use]
+    pub fn diff(&self, other: &Self) -> ProjectDiff {
+        let mut changes = Vec::new();
+        let mut field = |name: &'static str, changed: bool| {
+            if changed {
+                changes.push(ProjectChange::FieldChanged(name));
+            }
+        };
+        field("name", self.name != other.name);
+        field("comment", self.comment != other.comment);
+        field("output_dir", self.output_dir != other.output_dir);
+        field("cache_dir", self.cache_dir != other.cache_dir);
+        field("ustx_version", self.ustx_version != other.ustx_version);
+        field("resolution", self.resolution != other.resolution);
+        field("bpm", self.bpm.to_bits() != other.bpm.to_bits());
+        field("beat_per_bar", self.beat_per_bar != other.beat_per_bar);
+        field("beat_unit", self.beat_unit != other.beat_unit);
+        field("expressions", self.expressions != other.expressions);
+        field("exp_selectors", self.exp_selectors != other.exp_selectors);
+        field("exp_primary", self.exp_primary != other.exp_primary);
+        field("exp_secondary", self.exp_secondary != other.exp_secondary);
+        field("key", self.key != other.key);
+        field(
+            "time_signatures",
+            self.time_signatures != other.time_signatures,
+        );
+        field("tempos", self.tempos != other.tempos);
+
+        push_count_changes(
+            &mut changes,
+            self.tracks.len(),
+            other.tracks.len(),
+            ProjectChange::TrackAdded,
+            ProjectChange::TrackRemoved,
+        );
+        push_count_changes(
+            &mut changes,
+            self.voice_parts.len(),
+            other.voice_parts.len(),
+            ProjectChange::VoicePartAdded,
+            ProjectChange::VoicePartRemoved,
+        );
+        for (part_index, (before, after)) in
+            self.voice_parts.iter().zip(&other.voice_parts).enumerate()
+        {
+            if before.notes.len() != after.notes.len() {
+                changes.push(ProjectChange::NoteCountChanged {
+                    part_index,
+                    before: before.notes.len(),
+                    after: after.notes.len(),
+                });
+            }
+        }
+        push_count_changes(
+            &mut changes,
+            self.wave_parts.len(),
+            other.wave_parts.len(),
+            ProjectChange::WavePartAdded,
+            ProjectChange::WavePartRemoved,
+        );
+
+        ProjectDiff { changes }
+    }
+}
+
+fn push_count_changes(
+    changes: &mut Vec<ProjectChange>,
+    before: usize,
+    after: usize,
+    added: fn(usize) -> ProjectChange,
+    removed: fn(usize) -> ProjectChange,
+) {
+    changes.extend((before..after).map(added));
+    changes.extend((after..before).map(removed));
+}
@@ -5,22 +5,90 @@ use std::fmt::{self, Display, Formatter};
 #[derive(Debug)]
 pub enum Error {
     /// An error that occurred during YAML serialization or deserialization.
+    #[cfg(feature = "yaml")]
     Yaml(serde_yaml::Error),
+    /// An error that occurred because a `.ust` file is malformed.
+    #[cfg(feature = "ust")]
+    Ust(String),
+    /// An error that occurred during RON deserialization, with the span of the input
+    /// where it was found.
+    #[cfg(feature = "ron")]
+    Ron(ron::error::SpannedError),
+    /// An error that occurred during RON serialization.
+    #[cfg(feature = "ron")]
+    RonSerialize(ron::Error),
+    /// An error that occurred while reading an audio file.
+    #[cfg(feature = "audio")]
+    Audio(hound::Error),
+    /// An error that occurred while reading or writing data.
+    Io(std::io::Error),
+    /// An error that occurred because the input is not valid UTF-8.
+    Encoding(std::str::Utf8Error),
     /// An error that occurred because the `.ustx` version is not supported.
     UnsupportedVersion(String),
+    /// An error that occurred because the project resolution is not positive.
+    InvalidResolution(i32),
+    /// An error that occurred because a string is not a valid value of the expected kind.
+    InvalidValue {
+        /// A description of the expected kind of value.
+        expected: &'static str,
+        /// The string that failed to parse.
+        value: String,
+    },
+    /// An error that occurred because an expression descriptor was rejected.
+    InvalidExpression(String),
+    /// An error that occurred because strict parsing found an unrecognized key.
+    UnknownField {
+        /// The unrecognized key.
+        field: String,
+        /// The dotted path of the mapping containing the key, or `.` for the root.
+        location: String,
+    },
+    /// An error that occurred because no installed voicebank matches a track's singer.
+    SingerNotFound(String),
     /// An error that occurred because the YAML document is missing.
     MissingDocument,
+    /// An error that occurred because the input exceeds a parse limit.
+    LimitExceeded {
+        /// The name of the limit that was hit.
+        limit: &'static str,
+        /// The configured maximum.
+        max: usize,
+        /// The amount found in the input.
+        actual: usize,
+    },
 }
 
 impl Display for Error {
     #[inline]
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
+            #[cfg(feature = "yaml")]
             Self::Yaml(err) => write!(f, "yaml error: {err}"),
+            #[cfg(feature = "ust")]
+            Self::Ust(message) => write!(f, "ust error: {message}"),
+            #[cfg(feature = "ron")]
+            Self::Ron(err) => write!(f, "ron error: {err}"),
+            #[cfg(feature = "ron")]
+            Self::RonSerialize(err) => write!(f, "ron error: {err}"),
+            #[cfg(feature = "audio")]
+            Self::Audio(err) => write!(f, "audio error: {err}"),
+            Self::Io(err) => write!(f, "io error: {err}"),
+            Self::Encoding(err) => write!(f, "encoding error: {err}"),
             Self::UnsupportedVersion(version) => {
                 write!(f, "unsupported ustx version: {version}")
             }
+            Self::InvalidResolution(resolution) => write!(f, "invalid resolution: {resolution}"),
+            Self::InvalidValue { expected, value } => write!(f, "invalid {expected}: {value}"),
+            Self::InvalidExpression(message) => write!(f, "invalid expression: {message}"),
+            Self::UnknownField { field, location } => {
+                write!(f, "unknown field `{field}` at {location}")
+            }
+            Self::SingerNotFound(singer) => write!(f, "singer not found: {singer}"),
             Self::MissingDocument => write!(f, "missing yaml document"),
+            Self::LimitExceeded { limit, max, actual } => {
+                write!(f, "{limit} limit exceeded: {actual} > {max}")
+            }
         }
     }
 }
@@ -29,13 +97,31 @@ impl StdError for Error {
     #[inline]
     fn source(&self) -> Option<&(dyn StdError + 'static)> {
         match self {
+            #[cfg(feature = "yaml")]
             Self::Yaml(err) => Some(err),
-            Self::UnsupportedVersion(_) => None,
-            Self::MissingDocument => None,
+            #[cfg(feature = "ron")]
+            Self::Ron(err) => Some(err),
+            #[cfg(feature = "ron")]
+            Self::RonSerialize(err) => Some(err),
+            #[cfg(feature = "audio")]
+            Self::Audio(err) => Some(err),
+            Self::Io(err) => Some(err),
+            Self::Encoding(err) => Some(err),
+            #[cfg(feature = "ust")]
+            Self::Ust(_) => None,
+            Self::UnsupportedVersion(_)
+            | Self::InvalidResolution(_)
+            | Self::InvalidValue { .. }
+            | Self::InvalidExpression(_)
+            | Self::UnknownField { .. }
+            | Self::SingerNotFound(_)
+            | Self::MissingDocument
+            | Self::LimitExceeded { .. } => None,
         }
     }
 }
 
+#[cfg(feature = "yaml")]
 impl From<serde_yaml::Error> for Error {
     #[inline]
     fn from(value: serde_yaml::Error) -> Self {
@@ -43,10 +129,50 @@ impl From<serde_yaml::Error> for Error {
     }
 }
 
+#[cfg(feature = "ron")]
+impl From<ron::Error> for Error {
+    #[inline]
+    fn from(value: ron::Error) -> Self {
+        Self::RonSerialize(value)
+    }
+}
+
+#[cfg(feature = "ron")]
+impl From<ron::error::SpannedError> for Error {
+    #[inline]
+    fn from(value: ron::error::SpannedError) -> Self {
+        Self::Ron(value)
+    }
+}
+
+#[cfg(feature = "audio")]
+impl From<hound::Error> for Error {
+    #[inline]
+    fn from(value: hound::Error) -> Self {
+        Self::Audio(value)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    #[inline]
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
 impl Error {
     #[inline]
     #[must_use]
     pub fn unsupported_version<S: Into<String>>(version: S) -> Self {
         Self::UnsupportedVersion(version.into())
     }
+
+    #[inline]
+    #[must_use]
+    pub fn invalid_value<S: Into<String>>(expected: &'static str, value: S) -> Self {
+        Self::InvalidValue {
+            expected,
+            value: value.into(),
+        }
+    }
 }
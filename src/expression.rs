@@ -1,20 +1,73 @@
 use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+use crate::error::Error;
 
 /// Represents the type of an expression.
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
 #[serde(rename_all = "snake_case")]
 pub enum ExpressionType {
     /// A numerical expression, which can be represented by a single value.
     #[default]
+    #[serde(alias = "Numerical")]
     Numerical,
     /// An options expression, which can be one of a list of options.
+    #[serde(alias = "Options")]
     Options,
     /// A curve expression, which is represented by a curve.
+    #[serde(alias = "Curve")]
     Curve,
 }
 
+impl ExpressionType {
+    /// Returns the serialized name of the expression type.
+    #[inline]
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Numerical => "numerical",
+            Self::Options => "options",
+            Self::Curve => "curve",
+        }
+    }
+
+    /// Returns the name `OpenUtau` writes for the expression type.
+    #[inline]
+    #[must_use]
+    pub const fn openutau_name(self) -> &'static str {
+        match self {
+            Self::Numerical => "Numerical",
+            Self::Options => "Options",
+            Self::Curve => "Curve",
+        }
+    }
+}
+
+impl Display for ExpressionType {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ExpressionType {
+    type Err = Error;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "numerical" => Ok(Self::Numerical),
+            "options" => Ok(Self::Options),
+            "curve" => Ok(Self::Curve),
+            _ => Err(Error::invalid_value("expression type", s)),
+        }
+    }
+}
+
 /// Represents a descriptor for an expression.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub struct ExpressionDescriptor {
     /// The name of the expression.
@@ -44,8 +97,190 @@ pub struct ExpressionDescriptor {
     pub options: Vec<String>,
 }
 
+/// The abbreviations of the standard expressions `OpenUtau` declares in new projects, in
+/// the order it uses for the default expression selectors.
+pub const STANDARD_EXPRESSIONS: [&str; 10] = [
+    "dyn", "pitd", "clr", "eng", "vel", "vol", "atk", "dec", "gen", "bre",
+];
+
+/// The abbreviation of the voice color expression.
+pub(crate) const VOICE_COLOR_ABBR: &str = "clr";
+
+impl ExpressionDescriptor {
+    /// Returns `OpenUtau`'s built-in descriptor for the standard expression `abbr`, or
+    /// `None` if `abbr` is not in [`STANDARD_EXPRESSIONS`].
+    ///
+    /// The voice color expression (`clr`) gets the single unnamed color of a track
+    /// without a voicebank as its only option. Use
+    /// [`Track::voice_color_descriptor`](crate::Track::voice_color_descriptor) for the
+    /// colors of a particular singer.
+    #[must_use]
+    pub fn standard(abbr: &str) -> Option<Self> {
+        let (name, r#type, min, max, default_value, flag, options): (_, _, _, _, _, _, &[&str]) =
+            match abbr {
+                "dyn" => (
+                    "dynamics (curve)",
+                    ExpressionType::Curve,
+                    -240.0,
+                    120.0,
+                    0.0,
+                    None,
+                    &[],
+                ),
+                "pitd" => (
+                    "pitch deviation (curve)",
+                    ExpressionType::Curve,
+                    -1200.0,
+                    1200.0,
+                    0.0,
+                    None,
+                    &[],
+                ),
+                VOICE_COLOR_ABBR => return Some(Self::voice_color(vec![String::new()])),
+                "eng" => (
+                    "resampler engine",
+                    ExpressionType::Options,
+                    0.0,
+                    1.0,
+                    0.0,
+                    None,
+                    &["", "worldline"],
+                ),
+                "vel" => (
+                    "velocity",
+                    ExpressionType::Numerical,
+                    0.0,
+                    200.0,
+                    100.0,
+                    None,
+                    &[],
+                ),
+                "vol" => (
+                    "volume",
+                    ExpressionType::Numerical,
+                    0.0,
+                    200.0,
+                    100.0,
+                    None,
+                    &[],
+                ),
+                "atk" => (
+                    "attack",
+                    ExpressionType::Numerical,
+                    0.0,
+                    200.0,
+                    100.0,
+                    None,
+                    &[],
+                ),
+                "dec" => (
+                    "decay",
+                    ExpressionType::Numerical,
+                    0.0,
+                    100.0,
+                    0.0,
+                    None,
+                    &[],
+                ),
+                "gen" => (
+                    "gender",
+                    ExpressionType::Numerical,
+                    -100.0,
+                    100.0,
+                    0.0,
+                    Some("g"),
+                    &[],
+                ),
+                "bre" => (
+                    "breath",
+                    ExpressionType::Numerical,
+                    0.0,
+                    100.0,
+                    0.0,
+                    Some("B"),
+                    &[],
+                ),
+                _ => return None,
+            };
+        Some(Self {
+            name: String::from(name),
+            abbr: String::from(abbr),
+            r#type,
+            min,
+            max,
+            default_value,
+            is_flag: flag.is_some(),
+            flag: flag.map(String::from),
+            options: options.iter().map(|&option| String::from(option)).collect(),
+        })
+    }
+
+    /// Returns the voice color (`clr`) descriptor offering `options` as the colors.
+    pub(crate) fn voice_color(options: Vec<String>) -> Self {
+        let last = options.len().saturating_sub(1);
+        Self {
+            name: String::from("voice color"),
+            abbr: String::from(VOICE_COLOR_ABBR),
+            r#type: ExpressionType::Options,
+            min: 0.0,
+            max: f32::from(u16::try_from(last).unwrap_or(u16::MAX)),
+            default_value: 0.0,
+            is_flag: false,
+            flag: None,
+            options,
+        }
+    }
+
+    /// Returns the option an options expression `value` selects.
+    ///
+    /// `value` is rounded to the nearest index into `options`. Returns `None` if the
+    /// index is negative, not finite, or past the end of `options`.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    #[must_use]
+    pub fn option_name(&self, value: f32) -> Option<&str> {
+        let index = value.round();
+        if !index.is_finite() || index < 0.0 {
+            return None;
+        }
+        self.options.get(index as usize).map(String::as_str)
+    }
+
+    /// Returns the expression value that selects the option `name`, which is its index
+    /// in `options`.
+    #[must_use]
+    pub fn option_index(&self, name: &str) -> Option<i32> {
+        self.options
+            .iter()
+            .position(|option| option == name)
+            .and_then(|index| i32::try_from(index).ok())
+    }
+
+    /// Checks that the descriptor is usable.
+    ///
+    /// An options descriptor must have at least one option, and numerical and curve
+    /// descriptors must have `min <= max`. Violations are reported as
+    /// [`Error::InvalidExpression`].
+    pub fn validate(&self) -> Result<(), Error> {
+        let abbr = &self.abbr;
+        match self.r#type {
+            ExpressionType::Options if self.options.is_empty() => {
+                Err(Error::InvalidExpression(format!("`{abbr}` has no options")))
+            }
+            ExpressionType::Numerical | ExpressionType::Curve
+                if self.min.partial_cmp(&self.max).is_none_or(Ordering::is_gt) =>
+            {
+                Err(Error::InvalidExpression(format!(
+                    "`{abbr}` minimum {} is greater than maximum {}",
+                    self.min, self.max
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
 /// Represents an instance of an expression.
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
 #[serde(rename_all = "snake_case")]
 pub struct Expression {
     /// The index of the expression.
@@ -59,8 +294,68 @@ pub struct Expression {
     pub value: f32,
 }
 
+impl Expression {
+    /// Creates a numerical expression with the given abbreviation and value.
+    #[inline]
+    #[must_use]
+    pub fn numerical(abbr: impl Into<String>, value: f32) -> Self {
+        Self {
+            index: None,
+            abbr: abbr.into(),
+            value,
+        }
+    }
+
+    /// Sets `index` to the position of `abbr` in `selectors`, or `None` if it is not
+    /// listed.
+    #[inline]
+    pub fn sync_index(&mut self, selectors: &[String]) {
+        self.index = selectors
+            .iter()
+            .position(|selector| *selector == self.abbr)
+            .and_then(|position| i32::try_from(position).ok());
+    }
+
+    /// Sets `abbr` to the selector at `index` in `selectors`.
+    ///
+    /// `abbr` is left unchanged if `index` is `None` or out of bounds.
+    #[inline]
+    pub fn sync_abbr(&mut self, selectors: &[String]) {
+        if let Some(selector) = self
+            .index
+            .and_then(|index| usize::try_from(index).ok())
+            .and_then(|index| selectors.get(index))
+        {
+            self.abbr.clone_from(selector);
+        }
+    }
+
+    /// Clamps the value into the `[min, max]` range of `descriptor`.
+    ///
+    /// The value is left unchanged if the descriptor's range is empty.
+    #[inline]
+    pub fn clamp(&mut self, descriptor: &ExpressionDescriptor) {
+        if descriptor.min <= descriptor.max {
+            self.value = self.value.clamp(descriptor.min, descriptor.max);
+        }
+    }
+
+    /// Returns whether the value is a valid option index for an `Options` descriptor.
+    ///
+    /// Always returns `false` for descriptors of any other type.
+    #[inline]
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn is_valid_option(&self, descriptor: &ExpressionDescriptor) -> bool {
+        matches!(descriptor.r#type, ExpressionType::Options)
+            && self.value >= 0.0
+            && self.value.fract() == 0.0
+            && (self.value as usize) < descriptor.options.len()
+    }
+}
+
 /// Represents a curve.
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
 #[serde(rename_all = "snake_case")]
 pub struct Curve {
     /// The abbreviation of the curve.
@@ -73,3 +368,19 @@ pub struct Curve {
     #[serde(default)]
     pub ys: Vec<i32>,
 }
+
+impl Curve {
+    /// Returns the curve's points as `(x, y)` pairs.
+    ///
+    /// If `xs` and `ys` have different lengths, which only happens in corrupted files,
+    /// the extra values of the longer one are ignored.
+    #[inline]
+    #[must_use]
+    pub fn to_points(&self) -> Vec<(i32, i32)> {
+        self.xs
+            .iter()
+            .copied()
+            .zip(self.ys.iter().copied())
+            .collect()
+    }
+}
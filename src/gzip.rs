@@ -0,0 +1,27 @@
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use std::io::{Read, Write};
+
+use crate::error::Error;
+use crate::project::Project;
+
+impl Project {
+    /// Deserializes a `Project` from a gzip-compressed YAML document, such as the
+    /// contents of a `.ustx.gz` file.
+    ///
+    /// Corrupt compressed data is reported as [`Error::Io`]; the decompressed document is
+    /// parsed like [`Project::from_reader`].
+    #[inline]
+    pub fn from_gzip_reader<R: Read>(reader: R) -> Result<Self, Error> {
+        Self::from_reader(GzDecoder::new(reader))
+    }
+
+    /// Serializes a `Project` as gzip-compressed YAML into `writer`.
+    pub fn to_gzip_writer<W: Write>(&self, writer: W) -> Result<(), Error> {
+        let mut encoder = GzEncoder::new(writer, Compression::default());
+        self.to_writer(&mut encoder)?;
+        encoder.finish()?;
+        Ok(())
+    }
+}
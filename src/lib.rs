@@ -14,23 +14,73 @@
 //! This crate allows you to read, write, and manipulate `OpenUtau` projects in Rust.
 //! It provides data structures for all the components of a `.ustx` file,
 //! including the project itself, tracks, parts, notes, and expressions.
+//!
+//! # Features
+//!
+//! - `yaml` (default): enables reading and writing `.ustx` YAML documents via `serde_yaml`.
+//!   Without it, the data types are still usable with any `serde` format.
+//! - `ust`: enables importing legacy UTAU `.ust` files with `Project::from_ust_str`.
+//! - `ron`: enables reading and writing projects as RON with `Project::from_ron_str` and
+//!   `Project::to_ron_string`.
+//! - `audio`: enables reading WAV files referenced by wave parts, such as with
+//!   `WavePart::compute_peaks`.
+//! - `gzip`: enables reading and writing gzip-compressed projects with
+//!   `Project::from_gzip_reader` and `Project::to_gzip_writer`. Implies `yaml`.
+//! - `midi`: enables exporting projects as Standard MIDI Files with `Project::to_midi`.
 
+mod approx;
+#[cfg(feature = "audio")]
+mod audio;
+#[cfg(feature = "yaml")]
+pub mod batch;
+mod boolish;
+pub mod diff;
 pub mod error;
 pub mod expression;
+#[cfg(feature = "gzip")]
+mod gzip;
+#[cfg(feature = "yaml")]
+pub mod limits;
+#[cfg(feature = "midi")]
+mod midi;
 pub mod note;
+#[cfg(feature = "yaml")]
+mod openutau;
 pub mod part;
 pub mod phoneme;
+pub mod pitch;
 pub mod project;
+pub mod resolution;
+pub mod scale;
+pub mod stats;
+#[cfg(feature = "yaml")]
+mod stream;
 pub mod time;
+pub mod timeline;
 pub mod track;
+#[cfg(feature = "ust")]
+mod ust;
+pub mod validation;
 pub mod version;
 
+#[cfg(feature = "yaml")]
+pub use batch::{ConvertOutcome, convert_dir};
+pub use diff::{ProjectChange, ProjectDiff};
 pub use error::Error;
-pub use expression::{Curve, Expression, ExpressionDescriptor, ExpressionType};
-pub use note::{Note, Pitch, PitchPoint, PitchPointShape, Vibrato};
+pub use expression::{
+    Curve, Expression, ExpressionDescriptor, ExpressionType, STANDARD_EXPRESSIONS,
+};
+#[cfg(feature = "yaml")]
+pub use limits::ParseLimits;
+pub use note::{Note, NoteBuilder, Pitch, PitchPoint, PitchPointShape, Vibrato};
 pub use part::{VoicePart, WavePart};
-pub use phoneme::PhonemeOverride;
-pub use project::Project;
-pub use time::{Tempo, TimeSignature};
-pub use track::{RenderSettings, Track};
+pub use phoneme::{Phoneme, PhonemeError, PhonemeOverride};
+pub use project::{NoteLocation, Project};
+pub use resolution::{LossyTick, ResolutionReport};
+pub use scale::Scale;
+pub use stats::ProjectStats;
+pub use time::{FractionalTicks, Millis, Tempo, Ticks, TimeSignature};
+pub use timeline::TimelineEvent;
+pub use track::{RemovedTrack, RenderSettings, Track};
+pub use validation::ValidationIssue;
 pub use version::{CURRENT_VERSION, Version};
@@ -0,0 +1,77 @@
+use serde::de::IgnoredAny;
+
+use crate::error::Error;
+use crate::project::Project;
+use crate::stream::{PartSink, stream_parts};
+
+/// Upper bounds enforced by [`Project::from_yaml_str_limited`] when parsing untrusted input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ParseLimits {
+    /// The maximum size of the input, in bytes.
+    pub max_bytes: usize,
+    /// The maximum number of voice and wave parts combined.
+    pub max_parts: usize,
+    /// The maximum number of notes across all voice parts.
+    pub max_notes: usize,
+}
+
+impl Default for ParseLimits {
+    /// Returns limits generous enough for any project made by hand: 64 MiB of input,
+    /// 10 000 parts, and 1 000 000 notes.
+    #[inline]
+    fn default() -> Self {
+        Self {
+            max_bytes: 64 * 1024 * 1024,
+            max_parts: 10_000,
+            max_notes: 1_000_000,
+        }
+    }
+}
+
+impl Project {
+    /// Deserializes a `Project` from a YAML string, rejecting input that exceeds `limits`.
+    ///
+    /// The input size is checked first. The parts and notes are then counted in a pass
+    /// that skips over their contents without building them, and the project is only
+    /// deserialized if both counts are within bounds. A bound that is hit is reported as
+    /// [`Error::LimitExceeded`].
+    pub fn from_yaml_str_limited(input: &str, limits: ParseLimits) -> Result<Self, Error> {
+        check_limit("bytes", input.len(), limits.max_bytes)?;
+        let mut counts = Counts::default();
+        stream_parts(input, &mut counts)?;
+        check_limit("parts", counts.parts, limits.max_parts)?;
+        check_limit("notes", counts.notes, limits.max_notes)?;
+        Self::from_yaml_str(input)
+    }
+}
+
+const fn check_limit(limit: &'static str, actual: usize, max: usize) -> Result<(), Error> {
+    if actual > max {
+        Err(Error::LimitExceeded { limit, max, actual })
+    } else {
+        Ok(())
+    }
+}
+
+/// The number of parts and notes found in a document.
+#[derive(Default)]
+struct Counts {
+    parts: usize,
+    notes: usize,
+}
+
+impl PartSink for Counts {
+    type Note = IgnoredAny;
+
+    fn voice_part(&mut self) {
+        self.parts += 1;
+    }
+
+    fn wave_part(&mut self) {
+        self.parts += 1;
+    }
+
+    fn note(&mut self, _: IgnoredAny) {
+        self.notes += 1;
+    }
+}
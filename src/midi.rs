@@ -0,0 +1,202 @@
+use crate::error::Error;
+use crate::project::Project;
+use crate::timeline::TimelineEvent;
+
+/// The velocity given to every exported note.
+const NOTE_VELOCITY: u8 = 100;
+
+impl Project {
+    /// Exports the project as a format 1 Standard MIDI File.
+    ///
+    /// The first track holds the tempo and time signature changes, followed by one track
+    /// per voice part, named after the part. Each note becomes a note-on and note-off
+    /// pair on channel 1 with its lyric as a lyric meta event, at the part's `position`
+    /// plus the note's. Events are taken from [`Project::timeline_events`], and events
+    /// before tick 0 are moved to tick 0. Notes whose duration is not positive are left
+    /// out, since their note-off would come before their note-on.
+    ///
+    /// The file's division is the project's `resolution`. Returns
+    /// [`Error::InvalidResolution`] if it does not fit a MIDI division, and
+    /// [`Error::InvalidValue`] for a tone outside `0..=127`, a tempo that is not positive,
+    /// or a beat unit that is not a power of two, and also if the gap between two events
+    /// of a track is too long for a MIDI delta time.
+    pub fn to_midi(&self) -> Result<Vec<u8>, Error> {
+        let division = u16::try_from(self.resolution)
+            .ok()
+            .filter(|&division| division > 0 && division < 0x8000)
+            .ok_or(Error::InvalidResolution(self.resolution))?;
+        let mut tracks = vec![TrackWriter::default(); self.voice_parts.len() + 1];
+        for event in self.timeline_events() {
+            let tick = u32::try_from(event.tick()).unwrap_or(0);
+            match event {
+                TimelineEvent::TempoChange { bpm, .. } => {
+                    if bpm.is_nan() || bpm <= 0.0 {
+                        return Err(Error::invalid_value("tempo", bpm.to_string()));
+                    }
+                    let micros = (60_000_000.0 / bpm)
+                        .round()
+                        .clamp(1.0, f64::from(0xFF_FFFF_u32));
+                    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                    let micros = (micros as u32).to_be_bytes();
+                    tracks[0].meta(tick, 0x51, &micros[1..])?;
+                }
+                TimelineEvent::TimeSignatureChange {
+                    beat_per_bar,
+                    beat_unit,
+                    ..
+                } => {
+                    let numerator = u8::try_from(beat_per_bar).map_err(|_| {
+                        Error::invalid_value("beats per bar", beat_per_bar.to_string())
+                    })?;
+                    let denominator = u32::try_from(beat_unit)
+                        .ok()
+                        .filter(|unit| unit.is_power_of_two())
+                        .and_then(|unit| u8::try_from(unit.trailing_zeros()).ok())
+                        .ok_or_else(|| Error::invalid_value("beat unit", beat_unit.to_string()))?;
+                    tracks[0].meta(tick, 0x58, &[numerator, denominator, 24, 8])?;
+                }
+                TimelineEvent::NoteOn {
+                    part_index,
+                    note_index,
+                    tone,
+                    ..
+                } => {
+                    let note = &self.voice_parts[part_index].notes[note_index];
+                    if note.duration <= 0 {
+                        continue;
+                    }
+                    let key = midi_key(tone)?;
+                    let track = &mut tracks[part_index + 1];
+                    track.meta(tick, 0x05, note.lyric.as_bytes())?;
+                    track.event(tick, &[0x90, key, NOTE_VELOCITY])?;
+                }
+                TimelineEvent::NoteOff {
+                    part_index,
+                    note_index,
+                    tone,
+                    ..
+                } => {
+                    if self.voice_parts[part_index].notes[note_index].duration <= 0 {
+                        continue;
+                    }
+                    let key = midi_key(tone)?;
+                    tracks[part_index + 1].event(tick, &[0x80, key, 0])?;
+                }
+                TimelineEvent::WavePartStart { .. } => {}
+            }
+        }
+
+        let track_count = u16::try_from(tracks.len())
+            .map_err(|_| Error::invalid_value("MIDI track count", tracks.len().to_string()))?;
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"MThd");
+        bytes.extend_from_slice(&6_u32.to_be_bytes());
+        bytes.extend_from_slice(&1_u16.to_be_bytes());
+        bytes.extend_from_slice(&track_count.to_be_bytes());
+        bytes.extend_from_slice(&division.to_be_bytes());
+        for (index, mut track) in tracks.into_iter().enumerate() {
+            let name = index
+                .checked_sub(1)
+                .map(|part_index| self.voice_parts[part_index].name.as_bytes());
+            track.finish(name)?;
+            bytes.extend_from_slice(b"MTrk");
+            let length = u32::try_from(track.data.len()).map_err(|_| {
+                Error::invalid_value("MIDI track length", track.data.len().to_string())
+            })?;
+            bytes.extend_from_slice(&length.to_be_bytes());
+            bytes.extend_from_slice(&track.data);
+        }
+        Ok(bytes)
+    }
+}
+
+/// Converts a tone to a MIDI key number, rejecting tones outside `0..=127`.
+fn midi_key(tone: i32) -> Result<u8, Error> {
+    u8::try_from(tone)
+        .ok()
+        .filter(|&key| key <= 127)
+        .ok_or_else(|| Error::invalid_value("MIDI tone", tone.to_string()))
+}
+
+/// Accumulates the events of one MIDI track chunk.
+#[derive(Debug, Clone, Default)]
+struct TrackWriter {
+    data: Vec<u8>,
+    tick: u32,
+}
+
+impl TrackWriter {
+    /// Writes a channel event at `tick`.
+    fn event(&mut self, tick: u32, bytes: &[u8]) -> Result<(), Error> {
+        self.delta(tick)?;
+        self.data.extend_from_slice(bytes);
+        Ok(())
+    }
+
+    /// Writes a meta event of type `kind` at `tick`.
+    fn meta(&mut self, tick: u32, kind: u8, payload: &[u8]) -> Result<(), Error> {
+        self.delta(tick)?;
+        self.data.extend_from_slice(&[0xFF, kind]);
+        write_length(&mut self.data, payload.len())?;
+        self.data.extend_from_slice(payload);
+        Ok(())
+    }
+
+    /// Writes the delta time from the previous event to `tick`.
+    fn delta(&mut self, tick: u32) -> Result<(), Error> {
+        write_variable_length(
+            &mut self.data,
+            tick.saturating_sub(self.tick),
+            "MIDI delta time",
+        )?;
+        self.tick = self.tick.max(tick);
+        Ok(())
+    }
+
+    /// Prepends the track name, if any, and appends the end-of-track event.
+    fn finish(&mut self, name: Option<&[u8]>) -> Result<(), Error> {
+        if let Some(name) = name {
+            let mut header = Vec::new();
+            header.push(0);
+            header.extend_from_slice(&[0xFF, 0x03]);
+            write_length(&mut header, name.len())?;
+            header.extend_from_slice(name);
+            self.data.splice(0..0, header);
+        }
+        self.data.extend_from_slice(&[0, 0xFF, 0x2F, 0]);
+        Ok(())
+    }
+}
+
+/// The largest value a MIDI variable-length quantity can hold, in four bytes.
+const MAX_VARIABLE_LENGTH: u32 = 0x0FFF_FFFF;
+
+/// Appends the length of a meta event payload as a MIDI variable-length quantity.
+fn write_length(bytes: &mut Vec<u8>, length: usize) -> Result<(), Error> {
+    let value = u32::try_from(length).unwrap_or(u32::MAX);
+    write_variable_length(bytes, value, "MIDI meta event length")
+}
+
+/// Appends `value` as a MIDI variable-length quantity, or returns
+/// [`Error::InvalidValue`] naming it as `what` if it does not fit in four bytes.
+fn write_variable_length(bytes: &mut Vec<u8>, value: u32, what: &'static str) -> Result<(), Error> {
+    if value > MAX_VARIABLE_LENGTH {
+        return Err(Error::invalid_value(what, value.to_string()));
+    }
+    let mut groups = [0_u8; 4];
+    let mut count = 0;
+    let mut rest = value;
+    loop {
+        groups[count] = (rest & 0x7F) as u8;
+        count += 1;
+        rest >>= 7;
+        if rest == 0 {
+            break;
+        }
+    }
+    for index in (0..count).rev() {
+        let continuation = if index == 0 { 0 } else { 0x80 };
+        bytes.push(groups[index] | continuation);
+    }
+    Ok(())
+}
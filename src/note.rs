@@ -1,10 +1,20 @@
-use serde::{Deserialize, Serialize};
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize};
 
-use crate::expression::Expression;
-use crate::phoneme::PhonemeOverride;
+use crate::boolish;
+use crate::error::Error;
+use crate::expression::{Expression, VOICE_COLOR_ABBR};
+use crate::phoneme::{Phoneme, PhonemeError, PhonemeOverride};
+use crate::pitch;
+use crate::project::Project;
+use crate::track::Track;
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
 
 /// Represents a note in a voice part.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub struct Note {
     /// The position of the note in ticks.
@@ -14,7 +24,11 @@ pub struct Note {
     #[serde(default = "default_note_duration")]
     pub duration: i32,
     /// The tone of the note, as a MIDI note number.
-    #[serde(default)]
+    ///
+    /// Some third-party exporters write the tone as a note name such as `"C4"`, which is
+    /// accepted when reading and converted to a MIDI note number. It is always written as
+    /// an integer.
+    #[serde(default, deserialize_with = "deserialize_tone")]
     pub tone: i32,
     /// The lyric of the note.
     #[serde(default = "default_note_lyric")]
@@ -36,20 +50,87 @@ pub struct Note {
     pub phoneme_indexes: Vec<i32>,
 }
 
+impl Default for Note {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            position: 0,
+            duration: default_note_duration(),
+            tone: 0,
+            lyric: default_note_lyric(),
+            pitch: Pitch::default(),
+            vibrato: Vibrato::default(),
+            phoneme_expressions: Vec::new(),
+            phoneme_overrides: Vec::new(),
+            phoneme_indexes: Vec::new(),
+        }
+    }
+}
+
+/// A builder for [`Note`], starting from the same defaults used when parsing.
+#[derive(Debug, Clone, Default)]
+pub struct NoteBuilder {
+    note: Note,
+}
+
+impl NoteBuilder {
+    /// Sets the position of the note in ticks.
+    #[inline]
+    #[must_use]
+    pub const fn position(mut self, position: i32) -> Self {
+        self.note.position = position;
+        self
+    }
+
+    /// Sets the duration of the note in ticks.
+    #[inline]
+    #[must_use]
+    pub const fn duration(mut self, duration: i32) -> Self {
+        self.note.duration = duration;
+        self
+    }
+
+    /// Sets the tone of the note, as a MIDI note number.
+    #[inline]
+    #[must_use]
+    pub const fn tone(mut self, tone: i32) -> Self {
+        self.note.tone = tone;
+        self
+    }
+
+    /// Sets the lyric of the note.
+    #[inline]
+    #[must_use]
+    pub fn lyric(mut self, lyric: impl Into<String>) -> Self {
+        self.note.lyric = lyric.into();
+        self
+    }
+
+    /// Builds the note.
+    #[inline]
+    #[must_use]
+    pub fn build(self) -> Note {
+        self.note
+    }
+}
+
 /// Represents the pitch data for a note.
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
 #[serde(rename_all = "snake_case")]
 pub struct Pitch {
     /// A list of pitch points.
     #[serde(default)]
     pub data: Vec<PitchPoint>,
     /// Whether to snap the first pitch point to the note's tone.
-    #[serde(default = "default_snap_first")]
+    #[serde(
+        default = "default_snap_first",
+        deserialize_with = "boolish::deserialize"
+    )]
     pub snap_first: bool,
 }
 
 /// Represents a single point in a pitch curve.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub struct PitchPoint {
     /// The x-coordinate of the pitch point, in ticks.
@@ -62,7 +143,7 @@ pub struct PitchPoint {
 }
 
 /// Represents the shape of a pitch point.
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum PitchPointShape {
     /// Ease in and out.
@@ -86,11 +167,226 @@ impl Default for PitchPointShape {
     }
 }
 
+impl Pitch {
+    /// Returns the pitch points as `OpenUtau` renders them after a note with `prev_tone`.
+    ///
+    /// When `snap_first` is set and there is a previous note, the first point is moved
+    /// to `(prev_tone - note_tone) * 100` cents so the curve starts as a portamento from
+    /// the previous note's pitch. Otherwise the points are returned unchanged.
+    #[must_use]
+    pub fn effective_points(&self, note_tone: i32, prev_tone: Option<i32>) -> Vec<PitchPoint> {
+        let mut points = self.data.clone();
+        if self.snap_first
+            && let Some(prev_tone) = prev_tone
+            && let Some(first) = points.first_mut()
+        {
+            first.y = pitch::cents_between(note_tone, prev_tone);
+        }
+        points
+    }
+
+    /// Returns the number of pitch points.
+    #[inline]
+    #[must_use]
+    pub const fn point_count(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Returns the curve's offset in cents at `x` ticks, interpolating between points
+    /// with the shape of the earlier point the way `OpenUtau` does.
+    ///
+    /// Before the first point and after the last one the curve stays at their values.
+    /// Returns `0.0` if there are no points.
+    #[must_use]
+    pub fn value_at(&self, x: f32) -> f32 {
+        let Some(first) = self.data.first() else {
+            return 0.0;
+        };
+        if x <= first.x {
+            return first.y;
+        }
+        self.data
+            .windows(2)
+            .find(|window| x < window[1].x)
+            .map_or_else(
+                || self.data.last().map_or(0.0, |last| last.y),
+                |window| interpolate(&window[0], &window[1], x),
+            )
+    }
+
+    /// Reduces the curve to at most `max_points` points.
+    ///
+    /// The curve is resampled at evenly spaced ticks between its first and last points,
+    /// which are kept as they are, and the new points are joined linearly. Curves that
+    /// are already small enough are left unchanged. `max_points` below 2 is treated as 2.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn thin_to(&mut self, max_points: usize) {
+        let max_points = max_points.max(2);
+        if self.data.len() <= max_points {
+            return;
+        }
+        let (Some(first), Some(last)) = (self.data.first(), self.data.last()) else {
+            return;
+        };
+        let (start, end) = (first.x, last.x);
+        let step = (end - start) / (max_points - 1) as f32;
+        let mut thinned = Vec::with_capacity(max_points);
+        thinned.push(PitchPoint {
+            shape: PitchPointShape::L,
+            ..first.clone()
+        });
+        for index in 1..max_points - 1 {
+            let x = step.mul_add(index as f32, start);
+            thinned.push(PitchPoint {
+                x,
+                y: self.value_at(x),
+                shape: PitchPointShape::L,
+            });
+        }
+        thinned.push(last.clone());
+        self.data = thinned;
+    }
+
+    /// Removes redundant pitch points using the Ramer–Douglas–Peucker algorithm.
+    ///
+    /// A point is dropped only if the straight line between its kept neighbors passes
+    /// within `epsilon` cents of it. The first and last points are always kept, and so is
+    /// every point whose shape differs from a neighbor's, so runs of different shapes are
+    /// never merged.
+    pub fn simplify(&mut self, epsilon: f32) {
+        let len = self.data.len();
+        if len < 3 {
+            return;
+        }
+        let mut keep = vec![false; len];
+        keep[0] = true;
+        keep[len - 1] = true;
+        for (index, window) in self.data.windows(3).enumerate() {
+            if window[0].shape != window[1].shape || window[2].shape != window[1].shape {
+                keep[index + 1] = true;
+            }
+        }
+        let mut start = 0;
+        for end in 1..len {
+            if keep[end] {
+                simplify_range(&self.data, start, end, epsilon, &mut keep);
+                start = end;
+            }
+        }
+        let mut index = 0;
+        self.data.retain(|_| {
+            index += 1;
+            keep[index - 1]
+        });
+    }
+}
+
+/// Interpolates between `from` and `to` at `x` using the shape of `from`.
+fn interpolate(from: &PitchPoint, to: &PitchPoint, x: f32) -> f32 {
+    use std::f32::consts::{FRAC_PI_2, PI};
+
+    let width = to.x - from.x;
+    if width <= f32::EPSILON {
+        return to.y;
+    }
+    let t = ((x - from.x) / width).clamp(0.0, 1.0);
+    let eased = match from.shape {
+        PitchPointShape::Io => (1.0 - (PI * t).cos()) / 2.0,
+        PitchPointShape::L => t,
+        PitchPointShape::I => 1.0 - (FRAC_PI_2 * t).cos(),
+        PitchPointShape::O => (FRAC_PI_2 * t).sin(),
+    };
+    (to.y - from.y).mul_add(eased, from.y)
+}
+
+/// Marks the points of `points[start..=end]` that must be kept to stay within `epsilon`
+/// cents of the original curve.
+fn simplify_range(
+    points: &[PitchPoint],
+    start: usize,
+    end: usize,
+    epsilon: f32,
+    keep: &mut [bool],
+) {
+    if end <= start + 1 {
+        return;
+    }
+    let (first, last) = (&points[start], &points[end]);
+    let mut farthest = start;
+    let mut max_distance = 0.0;
+    for (index, point) in points.iter().enumerate().take(end).skip(start + 1) {
+        let expected = if (last.x - first.x).abs() > f32::EPSILON {
+            first.y + (last.y - first.y) * (point.x - first.x) / (last.x - first.x)
+        } else {
+            first.y
+        };
+        let distance = (point.y - expected).abs();
+        if distance > max_distance {
+            max_distance = distance;
+            farthest = index;
+        }
+    }
+    if max_distance > epsilon {
+        keep[farthest] = true;
+        simplify_range(points, start, farthest, epsilon, keep);
+        simplify_range(points, farthest, end, epsilon, keep);
+    }
+}
+
+impl PitchPoint {
+    /// Returns the absolute frequency of the point, in hertz, for a note with the given
+    /// MIDI tone.
+    #[inline]
+    #[must_use]
+    pub fn absolute_hz(&self, note_tone: i32) -> f64 {
+        pitch::fractional_tone_to_hz(f64::from(note_tone) + f64::from(self.y) / 100.0)
+    }
+}
+
+impl PitchPointShape {
+    /// Returns the serialized name of the shape.
+    #[inline]
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Io => "io",
+            Self::L => "l",
+            Self::I => "i",
+            Self::O => "o",
+        }
+    }
+}
+
+impl Display for PitchPointShape {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for PitchPointShape {
+    type Err = Error;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "io" => Ok(Self::Io),
+            "l" => Ok(Self::L),
+            "i" => Ok(Self::I),
+            "o" => Ok(Self::O),
+            _ => Err(Error::invalid_value("pitch point shape", s)),
+        }
+    }
+}
+
 /// Represents the vibrato data for a note.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub struct Vibrato {
-    /// The length of the vibrato in milliseconds.
+    /// The length of the vibrato, as a percentage (0 to 100) of the note duration.
+    ///
+    /// The vibrato occupies the end of the note. Use [`Vibrato::length_ms`] for the
+    /// length in milliseconds.
     #[serde(default)]
     pub length: f32,
     /// The period of the vibrato in milliseconds.
@@ -99,16 +395,16 @@ pub struct Vibrato {
     /// The depth of the vibrato in cents.
     #[serde(default = "default_vibrato_depth")]
     pub depth: f32,
-    /// The fade-in time of the vibrato in milliseconds.
+    /// The fade-in time, as a percentage of the vibrato length.
     #[serde(default = "default_vibrato_in")]
     pub r#in: f32,
-    /// The fade-out time of the vibrato in milliseconds.
+    /// The fade-out time, as a percentage of the vibrato length.
     #[serde(default = "default_vibrato_out")]
     pub out: f32,
-    /// The shift of the vibrato in milliseconds.
+    /// The phase shift of the vibrato, as a percentage of the period.
     #[serde(default)]
     pub shift: f32,
-    /// The drift of the vibrato in cents.
+    /// The drift of the vibrato center, as a percentage of the depth.
     #[serde(default)]
     pub drift: f32,
     /// The volume link of the vibrato.
@@ -132,6 +428,329 @@ impl Default for Vibrato {
     }
 }
 
+impl Note {
+    /// Returns the serialized key names of the note's fields, in declaration order.
+    #[inline]
+    #[must_use]
+    pub const fn field_names() -> &'static [&'static str] {
+        &[
+            "position",
+            "duration",
+            "tone",
+            "lyric",
+            "pitch",
+            "vibrato",
+            "phoneme_expressions",
+            "phoneme_overrides",
+            "phoneme_indexes",
+        ]
+    }
+
+    /// Returns a builder for a note with default values.
+    #[inline]
+    #[must_use]
+    pub fn builder() -> NoteBuilder {
+        NoteBuilder::default()
+    }
+
+    /// Returns the tick at which the note ends, clamped to `i32::MAX`.
+    #[inline]
+    #[must_use]
+    pub const fn end(&self) -> i32 {
+        self.position.saturating_add(self.duration)
+    }
+
+    /// Returns the key the crate orders notes by: `(position, tone)`.
+    ///
+    /// Notes at the same position are ordered by ascending tone. Use this with
+    /// `sort_by_key` to sort notes the same way as [`Project::normalize`].
+    #[inline]
+    #[must_use]
+    pub const fn sort_key(&self) -> (i32, i32) {
+        (self.position, self.tone)
+    }
+
+    /// Returns whether the note continues the previous note's syllable.
+    ///
+    /// Connector notes have a lyric starting with `+`, or with the `...` prefix used
+    /// before `.ustx` 0.5, which the 0.5 migration rewrites to `+`.
+    #[inline]
+    #[must_use]
+    pub fn is_connector(&self) -> bool {
+        self.lyric.starts_with(CONNECTOR_PREFIX) || self.lyric.starts_with(LEGACY_CONNECTOR_PREFIX)
+    }
+
+    /// Returns whether the note is a rest.
+    ///
+    /// `OpenUtau` treats notes whose lyric is `R`, `r`, or empty as rests. Connector notes
+    /// (`+`) are not rests.
+    #[inline]
+    #[must_use]
+    pub fn is_rest(&self) -> bool {
+        matches!(self.lyric.as_str(), "" | "R" | "r")
+    }
+
+    /// Returns the number of points in the note's pitch curve.
+    #[inline]
+    #[must_use]
+    pub const fn total_pitch_points(&self) -> usize {
+        self.pitch.point_count()
+    }
+
+    /// Disables the note's vibrato, resetting it to [`Vibrato::none`].
+    #[inline]
+    pub fn clear_vibrato(&mut self) {
+        self.vibrato = Vibrato::none();
+    }
+
+    /// Checks the note's phoneme overrides and phoneme indexes for duplicate or
+    /// negative indexes.
+    pub fn validate_phonemes(&self) -> Result<(), PhonemeError> {
+        let mut seen = BTreeSet::new();
+        for phoneme_override in &self.phoneme_overrides {
+            if phoneme_override.index < 0 {
+                return Err(PhonemeError::NegativeOverrideIndex(phoneme_override.index));
+            }
+            if !seen.insert(phoneme_override.index) {
+                return Err(PhonemeError::DuplicateOverrideIndex(phoneme_override.index));
+            }
+        }
+        seen.clear();
+        for &index in &self.phoneme_indexes {
+            if index < 0 {
+                return Err(PhonemeError::NegativePhonemeIndex(index));
+            }
+            if !seen.insert(index) {
+                return Err(PhonemeError::DuplicatePhonemeIndex(index));
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies the note's phoneme overrides to the resolved phonemes in `base`.
+    ///
+    /// Each override is applied to the phoneme at its `index`, in storage order, so a
+    /// later override of the same index is applied on top of an earlier one. Overrides
+    /// with an index outside `base` are ignored.
+    pub fn apply_overrides(&self, base: &mut [Phoneme]) {
+        for phoneme_override in &self.phoneme_overrides {
+            if let Some(phoneme) = usize::try_from(phoneme_override.index)
+                .ok()
+                .and_then(|index| base.get_mut(index))
+            {
+                phoneme_override.apply(phoneme);
+            }
+        }
+    }
+
+    /// Resolves the note's phonemes from the symbols `base` produced by a phonemizer.
+    ///
+    /// The note's `phoneme_indexes` select which entries of `base` belong to the note, in
+    /// order; when empty, every entry does. Indexes outside `base` are skipped. Each
+    /// resolved phoneme starts with a zero offset, preutterance, and overlap, and the
+    /// overrides whose `index` matches its index into `base` are then applied as in
+    /// [`Note::apply_overrides`].
+    #[must_use]
+    pub fn resolved_phonemes(&self, base: &[String]) -> Vec<Phoneme> {
+        let indexes = if self.phoneme_indexes.is_empty() {
+            (0..base.len()).collect::<Vec<_>>()
+        } else {
+            self.phoneme_indexes
+                .iter()
+                .filter_map(|&index| usize::try_from(index).ok())
+                .filter(|&index| index < base.len())
+                .collect()
+        };
+        indexes
+            .into_iter()
+            .map(|index| {
+                let mut phoneme = Phoneme {
+                    phoneme: base[index].clone(),
+                    ..Phoneme::default()
+                };
+                for phoneme_override in &self.phoneme_overrides {
+                    if usize::try_from(phoneme_override.index) == Ok(index) {
+                        phoneme_override.apply(&mut phoneme);
+                    }
+                }
+                phoneme
+            })
+            .collect()
+    }
+
+    /// Returns the note's tone in scientific pitch notation, with C4 as MIDI tone 60.
+    ///
+    /// See [`pitch::tone_name`] to use a different middle C octave.
+    #[inline]
+    #[must_use]
+    pub fn tone_name(&self) -> String {
+        pitch::tone_name(self.tone, pitch::MIDDLE_C_OCTAVE)
+    }
+
+    /// Sets the note's tone from a name in scientific pitch notation, with C4 as MIDI
+    /// tone 60.
+    ///
+    /// The tone is left unchanged if `name` cannot be parsed. See
+    /// [`pitch::parse_tone_name`] for the accepted syntax.
+    #[inline]
+    pub fn set_tone_from_name(&mut self, name: &str) -> Result<(), Error> {
+        self.tone = pitch::parse_tone_name(name, pitch::MIDDLE_C_OCTAVE)?;
+        Ok(())
+    }
+
+    /// Resolves the value of the expression `abbr` for this note.
+    ///
+    /// The first matching entry in `phoneme_expressions` wins; otherwise the value falls
+    /// back to the project's descriptor through [`Project::effective_expression`].
+    #[must_use]
+    pub fn expression_value(&self, abbr: &str, project: &Project) -> Option<f32> {
+        let explicit = self
+            .phoneme_expressions
+            .iter()
+            .find(|expression| expression.abbr == abbr)
+            .map(|expression| expression.value);
+        project.effective_expression(abbr, explicit)
+    }
+
+    /// Returns the name of the voice color the note selects on `track`.
+    ///
+    /// The first `clr` entry in `phoneme_expressions` is read as an index into the
+    /// track's `voice_color_names`, rounded to the nearest integer. A note without a `clr`
+    /// entry uses the first color. Returns `None` if the index is negative, not finite,
+    /// or past the end of the list.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    #[must_use]
+    pub fn voice_color<'a>(&self, track: &'a Track) -> Option<&'a str> {
+        let value = self
+            .phoneme_expressions
+            .iter()
+            .find(|expression| expression.abbr == VOICE_COLOR_ABBR)
+            .map_or(0.0, |expression| expression.value)
+            .round();
+        if !value.is_finite() || value < 0.0 {
+            return None;
+        }
+        track
+            .voice_color_names
+            .get(value as usize)
+            .map(String::as_str)
+    }
+
+    /// Removes phoneme overrides that share an index, keeping the last override for
+    /// each index.
+    pub fn dedup_phoneme_overrides(&mut self) {
+        let mut seen = BTreeSet::new();
+        let mut kept = Vec::with_capacity(self.phoneme_overrides.len());
+        for phoneme_override in self.phoneme_overrides.drain(..).rev() {
+            if seen.insert(phoneme_override.index) {
+                kept.push(phoneme_override);
+            }
+        }
+        kept.reverse();
+        self.phoneme_overrides = kept;
+    }
+}
+
+/// Orders notes by [`Note::sort_key`].
+///
+/// Notes with the same position and tone are only comparable when they are equal, in
+/// which case they compare as [`Ordering::Equal`]; otherwise they are unordered and
+/// `partial_cmp` returns `None`. This keeps the ordering consistent with `PartialEq`.
+impl PartialOrd for Note {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match self.sort_key().cmp(&other.sort_key()) {
+            Ordering::Equal => (self == other).then_some(Ordering::Equal),
+            ordering => Some(ordering),
+        }
+    }
+}
+
+impl Vibrato {
+    /// Returns a disabled vibrato, with `length` 0 and every other field at its default.
+    #[inline]
+    #[must_use]
+    pub fn none() -> Self {
+        Self {
+            length: 0.0,
+            ..Self::default()
+        }
+    }
+
+    /// Returns a subtle vibrato over the latter part of the note.
+    #[inline]
+    #[must_use]
+    pub fn preset_light() -> Self {
+        Self {
+            length: 40.0,
+            period: 200.0,
+            depth: 15.0,
+            ..Self::default()
+        }
+    }
+
+    /// Returns a wide, fast vibrato over most of the note.
+    #[inline]
+    #[must_use]
+    pub fn preset_heavy() -> Self {
+        Self {
+            length: 75.0,
+            period: 150.0,
+            depth: 50.0,
+            r#in: 20.0,
+            ..Self::default()
+        }
+    }
+
+    /// Returns the length of the vibrato in milliseconds for a note lasting
+    /// `note_duration_ms` milliseconds.
+    ///
+    /// `length` is clamped to 0 to 100 percent first.
+    #[inline]
+    #[must_use]
+    pub fn length_ms(&self, note_duration_ms: f32) -> f32 {
+        note_duration_ms * self.length.clamp(0.0, 100.0) / 100.0
+    }
+
+    /// Returns the vibrato pitch offset, in cents, at `t_ms` milliseconds into a note
+    /// lasting `note_duration_ms` milliseconds.
+    ///
+    /// This mirrors `OpenUtau`'s evaluation: the vibrato occupies the last `length` percent
+    /// of the note, fades in over `in` percent and out over `out` percent of the vibrato,
+    /// is phase-shifted by `shift` percent of a period, and is offset by `drift` percent of
+    /// `depth`. Returns `0.0` when `length <= 0` or `t_ms` falls outside the vibrato.
+    #[must_use]
+    pub fn pitch_offset_at(&self, note_duration_ms: f32, t_ms: f32) -> f32 {
+        if self.length <= 0.0 || note_duration_ms <= 0.0 || self.period <= 0.0 {
+            return 0.0;
+        }
+        let length = self.length.min(100.0) / 100.0;
+        let n_pos = t_ms / note_duration_ms;
+        let n_period = self.period / note_duration_ms;
+        let n_start = 1.0 - length;
+        if n_pos < n_start || n_pos > 1.0 {
+            return 0.0;
+        }
+        let n_in = length * self.r#in / 100.0;
+        let n_out = length * self.out / 100.0;
+        let phase = (n_pos - n_start) / n_period + self.shift / 100.0;
+        let mut y = (2.0 * std::f32::consts::PI * phase)
+            .sin()
+            .mul_add(self.depth, self.depth * self.drift / 100.0);
+        if n_pos < n_start + n_in {
+            y *= (n_pos - n_start) / n_in;
+        } else if n_pos > 1.0 - n_out {
+            y *= (1.0 - n_pos) / n_out;
+        }
+        y
+    }
+}
+
+pub(crate) const CONNECTOR_PREFIX: char = '+';
+
+/// The connector prefix used before `.ustx` 0.5.
+pub(crate) const LEGACY_CONNECTOR_PREFIX: &str = "...";
+
 #[inline]
 const fn default_note_duration() -> i32 {
     120
@@ -166,3 +785,20 @@ const fn default_vibrato_in() -> f32 {
 const fn default_vibrato_out() -> f32 {
     10.0
 }
+
+/// Deserializes a tone given either as a MIDI note number or as a note name.
+fn deserialize_tone<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i32, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Tone {
+        Number(i32),
+        Name(String),
+    }
+
+    match Tone::deserialize(deserializer)? {
+        Tone::Number(tone) => Ok(tone),
+        Tone::Name(name) => name.trim().parse().or_else(|_| {
+            pitch::parse_tone_name(&name, pitch::MIDDLE_C_OCTAVE).map_err(D::Error::custom)
+        }),
+    }
+}
@@ -0,0 +1,207 @@
+use serde_yaml::{Mapping, Value};
+use std::fmt::Write;
+use std::str::FromStr;
+
+use crate::error::Error;
+use crate::expression::ExpressionType;
+use crate::project::Project;
+
+impl Project {
+    /// Serializes a `Project` to a YAML string using `OpenUtau`'s scalar conventions.
+    ///
+    /// Unlike [`Project::to_yaml_string`], this applies the following conventions of
+    /// `OpenUtau`'s writer:
+    ///
+    /// - Whole floats are written without a fractional part (`120`, not `120.0`).
+    /// - Null values are omitted, and so are empty `phoneme_indexes`, which `OpenUtau`
+    ///   does not write.
+    /// - Expression types are written in `PascalCase` (`Curve`), and `ustx_version` is
+    ///   written unquoted.
+    /// - Curve keys are ordered `xs`, `ys`, `abbr`.
+    ///
+    /// Other keys are written in this crate's field order, and only fields modeled by
+    /// this crate are written, so the output is not byte-identical to a file saved by
+    /// `OpenUtau`.
+    pub fn to_yaml_string_openutau_style(&self) -> Result<String, Error> {
+        let value = serde_yaml::to_value(self)?;
+        let mut output = String::new();
+        if let Value::Mapping(mapping) = &value {
+            write_mapping(&mut output, mapping, 0, Context::Project);
+        }
+        Ok(output)
+    }
+}
+
+/// The kind of object a mapping represents, for the few keys that need special handling.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Context {
+    Project,
+    Expressions,
+    Descriptor,
+    Curves,
+    Other,
+}
+
+impl Context {
+    fn child(self, key: &str) -> Self {
+        match (self, key) {
+            (Self::Project, "expressions") => Self::Expressions,
+            (Self::Expressions, _) => Self::Descriptor,
+            (_, "curves") => Self::Curves,
+            _ => Self::Other,
+        }
+    }
+}
+
+const CURVE_KEY_ORDER: [&str; 3] = ["xs", "ys", "abbr"];
+
+fn entries(mapping: &Mapping, context: Context) -> Vec<(&str, &Value)> {
+    let mut entries = mapping
+        .iter()
+        .filter_map(|(key, value)| Some((key.as_str()?, value)))
+        .filter(|&(key, value)| !is_omitted(key, value))
+        .collect::<Vec<_>>();
+    if context == Context::Curves {
+        entries.sort_by_key(|&(key, _)| {
+            CURVE_KEY_ORDER
+                .iter()
+                .position(|&ordered| ordered == key)
+                .unwrap_or(CURVE_KEY_ORDER.len())
+        });
+    }
+    entries
+}
+
+/// Returns whether `OpenUtau` would leave the entry out entirely.
+fn is_omitted(key: &str, value: &Value) -> bool {
+    value.is_null() || (key == "phoneme_indexes" && value.as_sequence().is_some_and(Vec::is_empty))
+}
+
+fn write_mapping(output: &mut String, mapping: &Mapping, indent: usize, context: Context) {
+    for (key, value) in entries(mapping, context) {
+        push_indent(output, indent);
+        write_entry(output, key, value, indent, context);
+    }
+}
+
+/// Writes `key: value`, assuming the indentation for the key has already been written.
+fn write_entry(output: &mut String, key: &str, value: &Value, indent: usize, context: Context) {
+    output.push_str(key);
+    output.push(':');
+    let child = context.child(key);
+    match value {
+        Value::Mapping(mapping) if entries(mapping, child).is_empty() => output.push_str(" {}\n"),
+        Value::Sequence(sequence) if sequence.is_empty() => output.push_str(" []\n"),
+        Value::Mapping(mapping) => {
+            output.push('\n');
+            write_mapping(output, mapping, indent + 2, child);
+        }
+        Value::Sequence(sequence) => {
+            output.push('\n');
+            for item in sequence {
+                write_item(output, item, indent, child);
+            }
+        }
+        _ => {
+            output.push(' ');
+            match (context, key) {
+                (Context::Project, "ustx_version") => {
+                    output.push_str(value.as_str().unwrap_or_default());
+                }
+                (Context::Descriptor, "type") => {
+                    let name = value.as_str().unwrap_or_default();
+                    match ExpressionType::from_str(name) {
+                        Ok(kind) => output.push_str(kind.openutau_name()),
+                        Err(_) => output.push_str(name),
+                    }
+                }
+                _ => write_scalar(output, value),
+            }
+            output.push('\n');
+        }
+    }
+}
+
+fn write_item(output: &mut String, item: &Value, indent: usize, context: Context) {
+    push_indent(output, indent);
+    output.push_str("- ");
+    match item {
+        Value::Mapping(mapping) => {
+            let entries = entries(mapping, context);
+            if entries.is_empty() {
+                output.push_str("{}\n");
+            }
+            for (index, (key, value)) in entries.into_iter().enumerate() {
+                if index > 0 {
+                    push_indent(output, indent + 2);
+                }
+                write_entry(output, key, value, indent + 2, context);
+            }
+        }
+        Value::Sequence(sequence) if sequence.is_empty() => output.push_str("[]\n"),
+        Value::Sequence(sequence) => {
+            output.push('\n');
+            for nested in sequence {
+                write_item(output, nested, indent + 2, context);
+            }
+        }
+        _ => {
+            write_scalar(output, item);
+            output.push('\n');
+        }
+    }
+}
+
+fn write_scalar(output: &mut String, value: &Value) {
+    match value {
+        Value::Bool(flag) => output.push_str(if *flag { "true" } else { "false" }),
+        Value::Number(number) => match number.as_f64() {
+            Some(float) if !number.is_i64() && !number.is_u64() => write_float(output, float),
+            _ => {
+                let _ = write!(output, "{number}");
+            }
+        },
+        Value::String(text) => write_string(output, text),
+        _ => {}
+    }
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn write_float(output: &mut String, float: f64) {
+    if float.is_nan() {
+        output.push_str(".nan");
+    } else if float.is_infinite() {
+        output.push_str(if float > 0.0 { ".inf" } else { "-.inf" });
+    } else if f64::from(float as f32).to_bits() == float.to_bits() {
+        // Single-precision fields are widened on the way into a `Value`, so print the
+        // shortest representation that round-trips through `f32`.
+        let _ = write!(output, "{}", float as f32);
+    } else {
+        let _ = write!(output, "{float}");
+    }
+}
+
+fn write_string(output: &mut String, text: &str) {
+    let quoted = serde_yaml::to_string(text).unwrap_or_default();
+    let quoted = quoted.strip_suffix('\n').unwrap_or(&quoted);
+    if quoted.contains('\n') {
+        output.push('"');
+        for character in text.chars() {
+            match character {
+                '"' => output.push_str("\\\""),
+                '\\' => output.push_str("\\\\"),
+                '\n' => output.push_str("\\n"),
+                '\r' => output.push_str("\\r"),
+                '\t' => output.push_str("\\t"),
+                _ => output.push(character),
+            }
+        }
+        output.push('"');
+    } else {
+        output.push_str(quoted);
+    }
+}
+
+fn push_indent(output: &mut String, indent: usize) {
+    output.extend(std::iter::repeat_n(' ', indent));
+}
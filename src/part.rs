@@ -1,12 +1,16 @@
 use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 
 use crate::expression::Curve;
-use crate::note::Note;
+use crate::note::{CONNECTOR_PREFIX, Note, Vibrato};
+use crate::project::Project;
+use crate::time::{FractionalTicks, Millis, Ticks};
 
 /// Represents a voice part in an `OpenUtau` project.
 ///
 /// A voice part contains a sequence of notes and expression curves.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub struct VoicePart {
     /// The name of the voice part.
@@ -29,10 +33,24 @@ pub struct VoicePart {
     pub curves: Vec<Curve>,
 }
 
+impl Default for VoicePart {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            name: default_part_name(),
+            comment: String::new(),
+            track_no: 0,
+            position: 0,
+            notes: Vec::new(),
+            curves: Vec::new(),
+        }
+    }
+}
+
 /// Represents a wave part in an `OpenUtau` project.
 ///
 /// A wave part contains a reference to an audio file.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub struct WavePart {
     /// The name of the wave part.
@@ -61,7 +79,431 @@ pub struct WavePart {
     pub trim_ms: f64,
 }
 
+impl VoicePart {
+    /// Returns a copy of the part containing only the notes fully inside the tick range
+    /// `[start_tick, end_tick)`, rebased so the range starts at tick 0.
+    ///
+    /// Ticks are relative to the part. Notes straddling either boundary are dropped
+    /// rather than truncated, so lyrics and phoneme data stay intact. Curve points
+    /// outside the range are dropped, a point interpolated from its neighbours is added
+    /// at each boundary the curve crosses, and the `xs` are rebased the same way.
+    /// The new part's `position` is moved by `start_tick`, so the slice keeps its place
+    /// on the timeline.
+    #[must_use]
+    pub fn slice(&self, start_tick: i32, end_tick: i32) -> Self {
+        let notes = self
+            .notes
+            .iter()
+            .filter(|note| note.position >= start_tick && note.end() <= end_tick)
+            .map(|note| {
+                let mut note = note.clone();
+                note.position = note.position.saturating_sub(start_tick);
+                note
+            })
+            .collect();
+        let curves = self
+            .curves
+            .iter()
+            .map(|curve| clip_curve(curve, start_tick, end_tick, start_tick))
+            .collect();
+        Self {
+            name: self.name.clone(),
+            comment: self.comment.clone(),
+            track_no: self.track_no,
+            position: self.position.saturating_add(start_tick),
+            notes,
+            curves,
+        }
+    }
+
+    /// Splits the part at `tick`, relative to the part.
+    ///
+    /// The left part keeps the notes starting before `tick`, including any note that
+    /// straddles it, which stays intact. The right part gets the remaining notes rebased
+    /// to start at 0, and its `position` is moved by `tick` so the notes keep their place
+    /// on the timeline. Curve points are split and rebased the same way, with every
+    /// curve present in both halves and ending or starting at `tick` with the value the
+    /// curve has there.
+    #[must_use]
+    pub fn split_at(self, tick: i32) -> (Self, Self) {
+        let (left_notes, mut right_notes): (Vec<_>, Vec<_>) = self
+            .notes
+            .into_iter()
+            .partition(|note| note.position < tick);
+        for note in &mut right_notes {
+            note.position = note.position.saturating_sub(tick);
+        }
+        let (left_curves, right_curves) = self
+            .curves
+            .iter()
+            .map(|curve| {
+                (
+                    clip_curve(curve, i32::MIN, tick, 0),
+                    clip_curve(curve, tick, i32::MAX, tick),
+                )
+            })
+            .unzip();
+        let right = Self {
+            name: self.name.clone(),
+            comment: self.comment.clone(),
+            track_no: self.track_no,
+            position: self.position.saturating_add(tick),
+            notes: right_notes,
+            curves: right_curves,
+        };
+        let left = Self {
+            notes: left_notes,
+            curves: left_curves,
+            ..self
+        };
+        (left, right)
+    }
+
+    /// Splits every note longer than `max_duration` into a chain of tied notes.
+    ///
+    /// The first note of a chain keeps the original note's data with its duration cut to
+    /// `max_duration`. It is followed by connector notes (`+`) of at most `max_duration`
+    /// each, at the same tone and without pitch, vibrato, or phoneme data, so the chain
+    /// covers the original duration exactly. Nothing happens if `max_duration` is not
+    /// positive.
+    pub fn split_long_notes(&mut self, max_duration: i32) {
+        if max_duration <= 0 {
+            return;
+        }
+        let mut notes = Vec::with_capacity(self.notes.len());
+        for mut note in self.notes.drain(..) {
+            let end = note.end();
+            let tone = note.tone;
+            note.duration = note.duration.min(max_duration);
+            let mut position = note.end();
+            notes.push(note);
+            while position < end {
+                let duration = (end - position).min(max_duration);
+                notes.push(Note {
+                    position,
+                    duration,
+                    tone,
+                    lyric: CONNECTOR_PREFIX.to_string(),
+                    vibrato: Vibrato::none(),
+                    ..Note::default()
+                });
+                position += duration;
+            }
+        }
+        self.notes = notes;
+    }
+
+    /// Transposes the part by whole octaves so that as many notes as possible fall within
+    /// `low..=high`, returning the shift in semitones.
+    ///
+    /// Among the shifts that leave the fewest notes out of range, the smallest one is
+    /// chosen, preferring upward on a tie. Notes that are still out of range are left
+    /// as they are. Nothing happens if the part has no notes or `low > high`.
+    pub fn fit_to_range(&mut self, low: i32, high: i32) -> i32 {
+        if self.notes.is_empty() || low > high {
+            return 0;
+        }
+        let out_of_range = |shift: i32| {
+            self.notes
+                .iter()
+                .filter(|note| {
+                    let tone = i64::from(note.tone) + i64::from(shift);
+                    !(i64::from(low)..=i64::from(high)).contains(&tone)
+                })
+                .count()
+        };
+        let shift = (0..=MAX_OCTAVE_SHIFT)
+            .flat_map(|octaves| [octaves * 12, -octaves * 12])
+            .min_by_key(|&shift| out_of_range(shift))
+            .unwrap_or(0);
+        for note in &mut self.notes {
+            note.tone = note.tone.saturating_add(shift);
+        }
+        shift
+    }
+
+    /// Delays every note on an off-beat of a `grid_ticks` grid by `ratio` of the grid
+    /// interval, giving the part a swing feel.
+    ///
+    /// Off-beats are the odd grid lines: with an eighth-note grid of 240 ticks, the notes
+    /// at 240, 720 and so on. Notes off the grid, and all durations, are left unchanged.
+    /// `ratio` is clamped to `0.0..=1.0`, with NaN treated as 0. Nothing happens if
+    /// `grid_ticks` is not positive.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn apply_swing(&mut self, grid_ticks: i32, ratio: f32) {
+        if grid_ticks <= 0 {
+            return;
+        }
+        let ratio = if ratio.is_nan() {
+            0.0
+        } else {
+            ratio.clamp(0.0, 1.0)
+        };
+        let delay = (f64::from(grid_ticks) * f64::from(ratio)).round() as i32;
+        for note in &mut self.notes {
+            if note.position % grid_ticks == 0 && (note.position / grid_ticks) % 2 != 0 {
+                note.position = note.position.saturating_add(delay);
+            }
+        }
+    }
+
+    /// Moves every note by a random offset of up to `max_jitter_ticks` in either
+    /// direction, never before tick 0.
+    ///
+    /// The offsets come from a generator seeded with `seed`, so the same seed always
+    /// produces the same result. Durations are left unchanged.
+    pub fn humanize(&mut self, max_jitter_ticks: i32, seed: u64) {
+        if max_jitter_ticks <= 0 {
+            return;
+        }
+        let mut rng = SplitMix64(seed);
+        let span = u64::from(max_jitter_ticks.unsigned_abs()) * 2 + 1;
+        for note in &mut self.notes {
+            let offset =
+                i64::try_from(rng.next() % span).unwrap_or(0) - i64::from(max_jitter_ticks);
+            let position = (i64::from(note.position) + offset).max(0);
+            note.position = i32::try_from(position).unwrap_or(note.position);
+        }
+    }
+
+    /// Returns the pitch deviation (`pitd`) curve, if the part has one.
+    ///
+    /// Its `xs` are ticks relative to the part and its `ys` are offsets in cents.
+    #[inline]
+    #[must_use]
+    pub fn pitch_curve(&self) -> Option<&Curve> {
+        self.curves
+            .iter()
+            .find(|curve| curve.abbr == PITCH_DEVIATION_ABBR)
+    }
+
+    /// Appends `note`, placing it right after the end of the last note if its position
+    /// is left at 0.
+    pub fn push_note_after(&mut self, mut note: Note) {
+        if note.position == 0
+            && let Some(end) = self.notes.iter().map(Note::end).max()
+        {
+            note.position = end;
+        }
+        self.notes.push(note);
+    }
+
+    /// Returns the earliest note start and the latest note end, in ticks relative to the
+    /// part, or `None` if the part has no notes.
+    #[must_use]
+    pub fn tick_bounds(&self) -> Option<(i32, i32)> {
+        let start = self.notes.iter().map(|note| note.position).min()?;
+        let end = self.notes.iter().map(Note::end).max()?;
+        Some((start, end))
+    }
+
+    /// Returns the indexes of notes with a non-positive duration.
+    #[must_use]
+    pub fn invalid_duration_notes(&self) -> Vec<usize> {
+        self.notes
+            .iter()
+            .enumerate()
+            .filter(|(_, note)| note.duration <= 0)
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Raises every note shorter than `min_duration` to `min_duration`, returning how many
+    /// notes were changed.
+    pub fn fix_durations(&mut self, min_duration: i32) -> usize {
+        let mut fixed = 0;
+        for note in &mut self.notes {
+            if note.duration < min_duration {
+                note.duration = min_duration;
+                fixed += 1;
+            }
+        }
+        fixed
+    }
+
+    /// Removes every rest note (see [`Note::is_rest`]), returning how many were removed.
+    pub fn remove_rests(&mut self) -> usize {
+        let before = self.notes.len();
+        self.notes.retain(|note| !note.is_rest());
+        before - self.notes.len()
+    }
+
+    /// Returns every rest in the part as `(start_tick, length)`, in tick order.
+    ///
+    /// Notes are considered in position order regardless of how they are stored. A gap
+    /// between the start of the part and the first note counts as a rest, while
+    /// back-to-back or overlapping notes produce none.
+    #[must_use]
+    pub fn rests(&self) -> Vec<(i32, i32)> {
+        let mut rests = Vec::new();
+        let mut cursor = 0;
+        for (start, end) in self.sorted_spans() {
+            if start > cursor {
+                rests.push((cursor, start - cursor));
+            }
+            cursor = cursor.max(end);
+        }
+        rests
+    }
+
+    /// Returns whether the notes follow each other without any rest in between.
+    ///
+    /// Unlike [`VoicePart::rests`], a gap before the first note is not considered a break.
+    #[must_use]
+    pub fn is_continuous(&self) -> bool {
+        let mut spans = self.sorted_spans().into_iter();
+        let Some((_, mut cursor)) = spans.next() else {
+            return true;
+        };
+        for (start, end) in spans {
+            if start > cursor {
+                return false;
+            }
+            cursor = cursor.max(end);
+        }
+        true
+    }
+
+    /// Returns the `(start, end)` tick span of every note, sorted by start.
+    fn sorted_spans(&self) -> Vec<(i32, i32)> {
+        let mut spans = self
+            .notes
+            .iter()
+            .map(|note| (note.position, note.end()))
+            .collect::<Vec<_>>();
+        spans.sort_unstable();
+        spans
+    }
+
+    /// Groups notes into syllables, returned as ranges of note indices in storage order.
+    ///
+    /// A syllable is a note followed by any connector notes continuing it, as told by
+    /// [`Note::is_connector`]. A connector at the start of the part begins a
+    /// syllable of its own.
+    #[must_use]
+    pub fn syllables(&self) -> Vec<Range<usize>> {
+        let mut syllables: Vec<Range<usize>> = Vec::new();
+        for (index, note) in self.notes.iter().enumerate() {
+            match syllables.last_mut() {
+                Some(syllable) if note.is_connector() => syllable.end = index + 1,
+                _ => syllables.push(index..index + 1),
+            }
+        }
+        syllables
+    }
+
+    /// Replaces every note lyric with the result of `f`, leaving connector notes untouched.
+    pub fn map_lyrics<F: FnMut(&str) -> String>(&mut self, mut f: F) {
+        for note in &mut self.notes {
+            if !note.is_connector() {
+                note.lyric = f(&note.lyric);
+            }
+        }
+    }
+}
+
+impl WavePart {
+    /// Returns the length of audio actually played, in milliseconds.
+    ///
+    /// This is `file_duration_ms` minus `skip_ms` and `trim_ms`, and never negative.
+    #[inline]
+    #[must_use]
+    pub fn effective_duration_ms(&self) -> Millis {
+        Millis((self.file_duration_ms - self.skip_ms - self.trim_ms).max(0.0))
+    }
+
+    /// Returns the part's length on the timeline in ticks.
+    ///
+    /// The effective duration is converted through the project's tempo map starting at
+    /// the part's `position`, so tempo changes under the part are taken into account.
+    #[must_use]
+    pub fn duration_ticks(&self, project: &Project) -> Ticks {
+        let Millis(start_ms) = project.tick_to_ms(Ticks(self.position));
+        let Millis(duration_ms) = self.effective_duration_ms();
+        let FractionalTicks(end_tick) = project.ms_to_tick(Millis(start_ms + duration_ms));
+        FractionalTicks(end_tick - f64::from(self.position)).round()
+    }
+
+    /// Resolves the audio file path against the project directory.
+    #[inline]
+    #[must_use]
+    pub fn resolve_path(&self, project_dir: &Path) -> PathBuf {
+        project_dir.join(&self.relative_path)
+    }
+
+    /// Returns whether the referenced audio file exists relative to the project directory.
+    #[inline]
+    #[must_use]
+    pub fn audio_exists(&self, project_dir: &Path) -> bool {
+        self.resolve_path(project_dir).is_file()
+    }
+}
+
+/// The largest number of octaves [`VoicePart::fit_to_range`] considers shifting by,
+/// enough to move a note across the whole MIDI range.
+const MAX_OCTAVE_SHIFT: i32 = 11;
+
+const PITCH_DEVIATION_ABBR: &str = "pitd";
+
+/// A small deterministic pseudo-random number generator, used for reproducible
+/// humanization.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    const fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
 #[inline]
+/// Returns the points of `curve` between `start` and `end` inclusive, shifted left by
+/// `shift`.
+///
+/// If the curve crosses `start` or `end` between two points, a point linearly
+/// interpolated from them is added there, so the clipped curve keeps its shape up to the
+/// boundary.
+fn clip_curve(curve: &Curve, start: i32, end: i32, shift: i32) -> Curve {
+    let points = curve.to_points();
+    let mut clipped = Vec::new();
+    if start <= end {
+        clipped.extend(interpolate_curve(&points, start).map(|y| (start, y)));
+        clipped.extend(points.iter().filter(|&&(x, _)| start <= x && x <= end));
+        if start < end {
+            clipped.extend(interpolate_curve(&points, end).map(|y| (end, y)));
+        }
+    }
+    let (xs, ys) = clipped
+        .into_iter()
+        .map(|(x, y)| (x.saturating_sub(shift), y))
+        .unzip();
+    Curve {
+        abbr: curve.abbr.clone(),
+        xs,
+        ys,
+    }
+}
+
+/// Returns the value at `x` of the line between the two points of `points` that `x`
+/// falls strictly between, or `None` if there are no such points.
+#[allow(clippy::cast_possible_truncation)]
+fn interpolate_curve(points: &[(i32, i32)], x: i32) -> Option<i32> {
+    points.windows(2).find_map(|pair| match *pair {
+        [(x0, y0), (x1, y1)] if x0 < x && x < x1 => {
+            let t = (f64::from(x) - f64::from(x0)) / (f64::from(x1) - f64::from(x0));
+            Some(
+                (f64::from(y1) - f64::from(y0))
+                    .mul_add(t, f64::from(y0))
+                    .round() as i32,
+            )
+        }
+        _ => None,
+    })
+}
+
 fn default_part_name() -> String {
     String::from("New Part")
 }
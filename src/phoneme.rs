@@ -1,7 +1,9 @@
 use serde::{Deserialize, Serialize};
+use std::error::Error as StdError;
+use std::fmt::{self, Display, Formatter};
 
 /// Represents a phoneme override.
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
 #[serde(rename_all = "snake_case")]
 pub struct PhonemeOverride {
     /// The index of the phoneme to override.
@@ -20,3 +22,78 @@ pub struct PhonemeOverride {
     #[serde(default)]
     pub overlap_delta: Option<f32>,
 }
+
+/// Represents a resolved phoneme of a note, as produced by a phonemizer.
+///
+/// The `.ustx` format does not store these; they are the base that a note's
+/// [`PhonemeOverride`]s are applied to with [`Note::apply_overrides`].
+///
+/// [`Note::apply_overrides`]: crate::Note::apply_overrides
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct Phoneme {
+    /// The phoneme symbol.
+    #[serde(default)]
+    pub phoneme: String,
+    /// The offset of the phoneme from the note start, in ticks.
+    #[serde(default)]
+    pub offset: i32,
+    /// The preutterance of the phoneme, in milliseconds.
+    #[serde(default)]
+    pub preutter: f32,
+    /// The overlap of the phoneme, in milliseconds.
+    #[serde(default)]
+    pub overlap: f32,
+}
+
+impl PhonemeOverride {
+    /// Applies the override's present fields to `phoneme`.
+    ///
+    /// `phoneme` and `offset` replace the base values, while the deltas are added.
+    #[inline]
+    pub fn apply(&self, phoneme: &mut Phoneme) {
+        if let Some(symbol) = &self.phoneme {
+            phoneme.phoneme.clone_from(symbol);
+        }
+        if let Some(offset) = self.offset {
+            phoneme.offset = offset;
+        }
+        if let Some(delta) = self.preutter_delta {
+            phoneme.preutter += delta;
+        }
+        if let Some(delta) = self.overlap_delta {
+            phoneme.overlap += delta;
+        }
+    }
+}
+
+/// Represents an inconsistency in a note's phoneme data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PhonemeError {
+    /// More than one phoneme override targets the same index.
+    DuplicateOverrideIndex(i32),
+    /// A phoneme override targets a negative index.
+    NegativeOverrideIndex(i32),
+    /// A phoneme index appears more than once.
+    DuplicatePhonemeIndex(i32),
+    /// A phoneme index is negative.
+    NegativePhonemeIndex(i32),
+}
+
+impl Display for PhonemeError {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DuplicateOverrideIndex(index) => {
+                write!(f, "duplicate phoneme override index: {index}")
+            }
+            Self::NegativeOverrideIndex(index) => {
+                write!(f, "negative phoneme override index: {index}")
+            }
+            Self::DuplicatePhonemeIndex(index) => write!(f, "duplicate phoneme index: {index}"),
+            Self::NegativePhonemeIndex(index) => write!(f, "negative phoneme index: {index}"),
+        }
+    }
+}
+
+impl StdError for PhonemeError {}
@@ -0,0 +1,113 @@
+use crate::error::Error;
+
+const A4_HZ: f64 = 440.0;
+const A4_TONE: f64 = 69.0;
+
+/// The octave number of middle C (MIDI tone 60) used by `OpenUtau`, as in `C4`.
+pub const MIDDLE_C_OCTAVE: i32 = 4;
+
+const PITCH_CLASS_NAMES: [&str; 12] = [
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+
+/// Converts a MIDI tone to its frequency in hertz.
+///
+/// This uses twelve-tone equal temperament with A4 = 440 Hz at MIDI tone 69.
+#[inline]
+#[must_use]
+pub fn tone_to_hz(tone: i32) -> f64 {
+    fractional_tone_to_hz(f64::from(tone))
+}
+
+/// Converts a frequency in hertz to a fractional MIDI tone.
+#[inline]
+#[must_use]
+pub fn hz_to_tone(hz: f64) -> f64 {
+    12.0f64.mul_add((hz / A4_HZ).log2(), A4_TONE)
+}
+
+/// Returns the interval from tone `a` to tone `b` in cents.
+#[inline]
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn cents_between(a: i32, b: i32) -> f32 {
+    ((i64::from(b) - i64::from(a)) * 100) as f32
+}
+
+/// Returns the scientific pitch name of a MIDI tone, such as `C4` or `A#3`.
+///
+/// Accidentals are always written as sharps. `middle_c_octave` is the octave number given
+/// to MIDI tone 60, usually [`MIDDLE_C_OCTAVE`].
+#[must_use]
+pub fn tone_name(tone: i32, middle_c_octave: i32) -> String {
+    let pitch_class = pitch_class_name(tone);
+    let octave = tone.div_euclid(12) - 5 + middle_c_octave;
+    format!("{pitch_class}{octave}")
+}
+
+/// Parses a scientific pitch name, such as `C4`, `a#3` or `Db-1`, into a MIDI tone.
+///
+/// The letter is case-insensitive and may be followed by any number of `#` or `b`
+/// accidentals, so enharmonic spellings like `B#3` and `Cb4` are accepted. The octave is
+/// interpreted with `middle_c_octave` as the octave of MIDI tone 60.
+pub fn parse_tone_name(name: &str, middle_c_octave: i32) -> Result<i32, Error> {
+    let invalid = || Error::invalid_value("tone name", name);
+    let (pitch_class, octave) = split_pitch_class(name.trim()).ok_or_else(invalid)?;
+    let octave = octave.parse::<i32>().map_err(|_| invalid())?;
+    octave
+        .checked_sub(middle_c_octave)
+        .and_then(|octave| octave.checked_add(5))
+        .and_then(|octave| octave.checked_mul(12))
+        .and_then(|tone| tone.checked_add(pitch_class))
+        .ok_or_else(invalid)
+}
+
+/// Returns the name of a pitch class, such as `C` or `F#`, counting semitones up from C.
+///
+/// Accidentals are always written as sharps, and `pitch_class` is wrapped into `0..12`,
+/// so `-1` is `B` and `12` is `C`.
+#[must_use]
+pub const fn pitch_class_name(pitch_class: i32) -> &'static str {
+    PITCH_CLASS_NAMES[pitch_class.rem_euclid(12) as usize]
+}
+
+/// Parses a pitch class name without an octave, such as `C`, `f#` or `Bb`, into
+/// semitones above C in `0..12`.
+///
+/// The accepted spellings are those of [`parse_tone_name`] without the octave.
+pub fn parse_pitch_class_name(name: &str) -> Result<i32, Error> {
+    match split_pitch_class(name.trim()) {
+        Some((pitch_class, "")) => Ok(pitch_class.rem_euclid(12)),
+        _ => Err(Error::invalid_value("pitch class name", name)),
+    }
+}
+
+/// Splits the leading letter and accidentals off `name`, returning the pitch class they
+/// spell, which is not wrapped into `0..12`, and the rest of the string.
+fn split_pitch_class(name: &str) -> Option<(i32, &str)> {
+    let mut chars = name.chars();
+    let mut pitch_class = match chars.next()?.to_ascii_uppercase() {
+        'C' => 0,
+        'D' => 2,
+        'E' => 4,
+        'F' => 5,
+        'G' => 7,
+        'A' => 9,
+        'B' => 11,
+        _ => return None,
+    };
+    let rest = chars.as_str();
+    let accidentals_end = rest
+        .find(|c: char| c != '#' && c != 'b')
+        .unwrap_or(rest.len());
+    for accidental in rest[..accidentals_end].chars() {
+        pitch_class += if accidental == '#' { 1 } else { -1 };
+    }
+    Some((pitch_class, &rest[accidentals_end..]))
+}
+
+/// Converts a fractional MIDI tone to its frequency in hertz.
+#[inline]
+pub(crate) fn fractional_tone_to_hz(tone: f64) -> f64 {
+    A4_HZ * ((tone - A4_TONE) / 12.0).exp2()
+}
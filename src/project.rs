@@ -1,11 +1,22 @@
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeMap;
+#[cfg(feature = "yaml")]
+use serde_yaml::{Mapping, Value};
+use std::collections::{BTreeMap, BTreeSet};
+#[cfg(feature = "yaml")]
 use std::fmt;
+#[cfg(feature = "yaml")]
+use std::io::{Read, Write};
+use std::ops::Index;
+use std::path::{Component, Path, PathBuf};
+#[cfg(feature = "yaml")]
 use std::str::FromStr;
 
 use crate::error::Error;
-use crate::expression::ExpressionDescriptor;
+use crate::expression::{ExpressionDescriptor, ExpressionType, STANDARD_EXPRESSIONS};
+use crate::note::{LEGACY_CONNECTOR_PREFIX, Note};
 use crate::part::{VoicePart, WavePart};
+use crate::pitch;
+use crate::scale::Scale;
 use crate::time::{Tempo, TimeSignature};
 use crate::track::Track;
 use crate::version::{CURRENT_VERSION, Version};
@@ -14,7 +25,7 @@ use crate::version::{CURRENT_VERSION, Version};
 ///
 /// This is the root object of a `.ustx` file. It contains all the project settings,
 /// tracks, parts, and other data.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub struct Project {
     /// The name of the project.
@@ -30,6 +41,10 @@ pub struct Project {
     #[serde(default = "default_cache_dir")]
     pub cache_dir: String,
     /// The version of the `.ustx` file format.
+    ///
+    /// `None` means the document has no `ustx_version` key (or it is null), which
+    /// [`Project::convert_to`] treats as [`Version::zero`]. A malformed version is a parse
+    /// error rather than being defaulted, so the two cases are never confused.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub ustx_version: Option<Version>,
     /// The resolution of the project, in ticks per quarter note.
@@ -76,31 +91,220 @@ pub struct Project {
     pub wave_parts: Vec<WavePart>,
 }
 
+/// Identifies a note by its voice part and its index within that part.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NoteLocation {
+    /// The index of the voice part in `Project::voice_parts`.
+    pub part_index: usize,
+    /// The index of the note in the part's `notes`.
+    pub note_index: usize,
+}
+
+impl Default for Project {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            name: default_project_name(),
+            comment: String::new(),
+            output_dir: default_output_dir(),
+            cache_dir: default_cache_dir(),
+            ustx_version: None,
+            resolution: default_resolution(),
+            bpm: default_bpm(),
+            beat_per_bar: default_beat_per_bar(),
+            beat_unit: default_beat_unit(),
+            expressions: BTreeMap::new(),
+            exp_selectors: default_exp_selectors(),
+            exp_primary: 0,
+            exp_secondary: default_exp_secondary(),
+            key: 0,
+            time_signatures: default_time_signatures(),
+            tempos: default_tempos(),
+            tracks: default_tracks(),
+            voice_parts: Vec::new(),
+            wave_parts: Vec::new(),
+        }
+    }
+}
+
+#[cfg(feature = "yaml")]
 impl Project {
     /// Deserializes a `Project` from a YAML string.
+    ///
+    /// A leading UTF-8 byte order mark, as written by some Windows editors, is ignored.
     #[inline]
     pub fn from_yaml_str(input: &str) -> Result<Self, Error> {
-        let mut documents = serde_yaml::Deserializer::from_str(input);
-        let document = documents.next().ok_or_else(|| Error::MissingDocument)?;
-        let project = Project::deserialize(document)?;
+        let mut documents = serde_yaml::Deserializer::from_str(strip_bom(input));
+        let document = documents.next().ok_or(Error::MissingDocument)?;
+        let project = Self::deserialize(document)?;
         Ok(project)
     }
 
+    /// Deserializes a `Project` from a YAML document given as raw bytes.
+    ///
+    /// Input that is not valid UTF-8 is rejected with [`Error::Encoding`]. This never
+    /// panics on arbitrary input, and is the target of the fuzz harness in `fuzz/`.
+    #[inline]
+    pub fn from_yaml_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let input = std::str::from_utf8(bytes).map_err(Error::Encoding)?;
+        Self::from_yaml_str(input)
+    }
+
+    /// Deserializes a `Project` from the first YAML document read from `reader`.
+    ///
+    /// Input that is not valid UTF-8 is rejected with [`Error::Encoding`].
+    #[inline]
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self, Error> {
+        let mut buffer = Vec::new();
+        reader.read_to_end(&mut buffer)?;
+        Self::from_yaml_bytes(&buffer)
+    }
+
+    /// Serializes a `Project` as YAML into `writer`.
+    #[inline]
+    pub fn to_writer<W: Write>(&self, writer: W) -> Result<(), Error> {
+        serde_yaml::to_writer(writer, self).map_err(Error::from)
+    }
+
+    /// Deserializes a `Project` from a YAML string, rejecting unrecognized keys.
+    ///
+    /// This is useful for linting hand-authored files, where an unknown key is usually a
+    /// typo that the tolerant [`Project::from_yaml_str`] would silently ignore. The first
+    /// unknown key is reported as [`Error::UnknownField`].
+    pub fn from_yaml_str_strict(input: &str) -> Result<Self, Error> {
+        let mut documents = serde_yaml::Deserializer::from_str(strip_bom(input));
+        let document = documents.next().ok_or(Error::MissingDocument)?;
+        let mut unknown = None;
+        let project = serde_ignored::deserialize(document, |path| {
+            if unknown.is_none() {
+                unknown = Some(match path {
+                    serde_ignored::Path::Map { parent, key } => (key, parent.to_string()),
+                    other => (other.to_string(), String::from(".")),
+                });
+            }
+        })?;
+        match unknown {
+            Some((field, location)) => Err(Error::UnknownField { field, location }),
+            None => Ok(project),
+        }
+    }
+
+    /// Deserializes a `Project` from a YAML string, skipping the parts that fail to parse.
+    ///
+    /// This is meant for recovering data from damaged files. Malformed tracks, voice
+    /// parts, notes and wave parts are dropped, and malformed top-level fields fall back
+    /// to their defaults. Every skipped item adds an error to the returned `Vec`. The
+    /// project is `None` only if the input is not a YAML mapping at all.
+    pub fn from_yaml_str_lossy(input: &str) -> (Option<Self>, Vec<Error>) {
+        let mut errors = Vec::new();
+        let mut value = match Self::first_document_value(input) {
+            Ok(value) => value,
+            Err(err) => return (None, vec![err]),
+        };
+        let Some(root) = value.as_mapping_mut() else {
+            let error =
+                Self::deserialize(value).map_or_else(Error::from, |_| Error::MissingDocument);
+            return (None, vec![error]);
+        };
+        if let Some(parts) = root.get_mut("voice_parts").and_then(Value::as_sequence_mut) {
+            for part in parts.iter_mut() {
+                if let Some(notes) = part.get_mut("notes").and_then(Value::as_sequence_mut) {
+                    retain_valid::<Note>(notes, &mut errors);
+                }
+            }
+            retain_valid::<VoicePart>(parts, &mut errors);
+        }
+        if let Some(tracks) = root.get_mut("tracks").and_then(Value::as_sequence_mut) {
+            retain_valid::<Track>(tracks, &mut errors);
+        }
+        if let Some(parts) = root.get_mut("wave_parts").and_then(Value::as_sequence_mut) {
+            retain_valid::<WavePart>(parts, &mut errors);
+        }
+        root.retain(|key, field| {
+            let single = Mapping::from_iter([(key.clone(), field.clone())]);
+            match Self::deserialize(Value::Mapping(single)) {
+                Ok(_) => true,
+                Err(err) => {
+                    errors.push(err.into());
+                    false
+                }
+            }
+        });
+        match Self::deserialize(value) {
+            Ok(project) => (Some(project), errors),
+            Err(err) => {
+                errors.push(err.into());
+                (None, errors)
+            }
+        }
+    }
+
+    fn first_document_value(input: &str) -> Result<Value, Error> {
+        let mut documents = serde_yaml::Deserializer::from_str(strip_bom(input));
+        let document = documents.next().ok_or(Error::MissingDocument)?;
+        Ok(Value::deserialize(document)?)
+    }
+
+    /// Deserializes every `---`-separated document in a YAML string into a `Project`.
+    ///
+    /// Empty documents, such as the one following a trailing `---`, are skipped, so an
+    /// empty input yields an empty `Vec`.
+    #[inline]
+    pub fn from_yaml_multi(input: &str) -> Result<Vec<Self>, Error> {
+        let mut projects = Vec::new();
+        for document in serde_yaml::Deserializer::from_str(strip_bom(input)) {
+            let value = Value::deserialize(document)?;
+            if value.is_null() {
+                continue;
+            }
+            projects.push(Self::deserialize(value)?);
+        }
+        Ok(projects)
+    }
+
     /// Serializes a `Project` to a YAML string.
     #[inline]
     pub fn to_yaml_string(&self) -> Result<String, Error> {
         serde_yaml::to_string(self).map_err(Error::from)
     }
 
+    /// Serializes a `Project` to a YAML string with every float rounded to `decimals`
+    /// places.
+    ///
+    /// Rounding applies to all floating-point fields, including nested ones such as pitch
+    /// points, vibrato, curves and expressions. The project itself is left untouched.
+    pub fn to_yaml_string_rounded(&self, decimals: u8) -> Result<String, Error> {
+        let mut value = serde_yaml::to_value(self)?;
+        round_floats(&mut value, 10_f64.powi(i32::from(decimals)));
+        serde_yaml::to_string(&value).map_err(Error::from)
+    }
+
+    /// Computes a stable hash of the project's content.
+    ///
+    /// The hash is a 64-bit FNV-1a digest of the serialized YAML, so it is identical
+    /// across runs and platforms for identical content. Hashing a project before and
+    /// after [`Project::convert_to`] tells whether the conversion changed anything.
+    ///
+    /// # Panics
+    ///
+    /// Does not panic in practice: every map key in a project is a string and the hasher
+    /// never fails to write, so serialization cannot fail.
+    #[must_use]
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = Fnv1a::new();
+        serde_yaml::to_writer(&mut hasher, self)
+            .expect("a project with string keys serializes into an infallible writer");
+        hasher.finish()
+    }
+
     /// Deserializes a `Project` from a YAML string with compatibility upgrades.
     ///
     /// This function will attempt to upgrade the project from older formats to the
-    /// current format.
+    /// current format. A `resolution` that is not positive is rejected with
+    /// [`Error::InvalidResolution`], since every tick-to-time conversion divides by it.
     #[inline]
     pub fn from_yaml_str_with_compat(input: &str) -> Result<Self, Error> {
-        let mut project = Self::from_yaml_str(input)?;
-        project.convert_to(CURRENT_VERSION)?;
-        Ok(project)
+        Self::from_yaml_str(input)?.upgraded()
     }
 
     /// Serializes a `Project` to a YAML string with compatibility upgrades.
@@ -110,6 +314,519 @@ impl Project {
         project.convert_to(CURRENT_VERSION)?;
         serde_yaml::to_string(&project).map_err(Error::from)
     }
+}
+
+#[cfg(feature = "ron")]
+impl Project {
+    /// Deserializes a `Project` from a RON string.
+    #[inline]
+    pub fn from_ron_str(input: &str) -> Result<Self, Error> {
+        ron::from_str(input).map_err(Error::from)
+    }
+
+    /// Serializes a `Project` to a pretty-printed RON string.
+    #[inline]
+    pub fn to_ron_string(&self) -> Result<String, Error> {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()).map_err(Error::from)
+    }
+}
+
+impl Project {
+    /// Returns the serialized key names of the project's fields, in declaration order.
+    ///
+    /// This is useful for tools that enumerate the schema, such as form generators.
+    /// [`Track::field_names`] and [`Note::field_names`] list the nested objects' keys.
+    #[inline]
+    #[must_use]
+    pub const fn field_names() -> &'static [&'static str] {
+        &[
+            "name",
+            "comment",
+            "output_dir",
+            "cache_dir",
+            "ustx_version",
+            "resolution",
+            "bpm",
+            "beat_per_bar",
+            "beat_unit",
+            "expressions",
+            "exp_selectors",
+            "exp_primary",
+            "exp_secondary",
+            "key",
+            "time_signatures",
+            "tempos",
+            "tracks",
+            "voice_parts",
+            "wave_parts",
+        ]
+    }
+
+    /// Returns an iterator over every note in every voice part, in storage order.
+    #[inline]
+    pub fn notes(&self) -> impl Iterator<Item = &Note> {
+        self.voice_parts.iter().flat_map(|part| part.notes.iter())
+    }
+
+    /// Calls `f` on every voice part, in storage order.
+    #[inline]
+    pub fn map_voice_parts<F: FnMut(&mut VoicePart)>(&mut self, f: F) {
+        self.voice_parts.iter_mut().for_each(f);
+    }
+
+    /// Calls `f` on every note in every voice part.
+    ///
+    /// Notes are visited in storage order, part by part, which is not necessarily
+    /// position order.
+    #[inline]
+    pub fn map_notes<F: FnMut(&mut Note)>(&mut self, f: F) {
+        self.voice_parts
+            .iter_mut()
+            .flat_map(|part| &mut part.notes)
+            .for_each(f);
+    }
+
+    /// Returns the location of every note matching `pred`, in storage order.
+    ///
+    /// The locations can be passed to [`Project::note_at`] and [`Project::note_at_mut`]
+    /// and stay valid until notes or voice parts are added, removed, or reordered.
+    pub fn find_notes<F: Fn(&Note) -> bool>(&self, pred: F) -> Vec<NoteLocation> {
+        self.voice_parts
+            .iter()
+            .enumerate()
+            .flat_map(|(part_index, part)| {
+                part.notes
+                    .iter()
+                    .enumerate()
+                    .map(move |(note_index, note)| (part_index, note_index, note))
+            })
+            .filter(|(_, _, note)| pred(note))
+            .map(|(part_index, note_index, _)| NoteLocation {
+                part_index,
+                note_index,
+            })
+            .collect()
+    }
+
+    /// Returns the note at `location`, or `None` if it is out of bounds.
+    #[inline]
+    #[must_use]
+    pub fn note_at(&self, location: NoteLocation) -> Option<&Note> {
+        self.voice_parts
+            .get(location.part_index)?
+            .notes
+            .get(location.note_index)
+    }
+
+    /// Returns a mutable reference to the note at `location`, or `None` if it is out of
+    /// bounds.
+    #[inline]
+    pub fn note_at_mut(&mut self, location: NoteLocation) -> Option<&mut Note> {
+        self.voice_parts
+            .get_mut(location.part_index)?
+            .notes
+            .get_mut(location.note_index)
+    }
+
+    /// Returns the track with the given index, as used by a part's `track_no`.
+    #[inline]
+    #[must_use]
+    pub fn track(&self, index: i32) -> Option<&Track> {
+        usize::try_from(index)
+            .ok()
+            .and_then(|index| self.tracks.get(index))
+    }
+
+    /// Returns the wave parts whose audio file cannot be found relative to `project_dir`.
+    #[must_use]
+    pub fn missing_wave_files(&self, project_dir: &Path) -> Vec<&WavePart> {
+        self.wave_parts
+            .iter()
+            .filter(|part| !part.audio_exists(project_dir))
+            .collect()
+    }
+
+    /// Rewrites every wave part's `relative_path` for a project moved from `old_root` to
+    /// `new_root`, so it still points at the same audio file.
+    ///
+    /// Relative roots are taken relative to the current directory. Paths are then
+    /// resolved lexically, without touching the file system. Wave part paths,
+    /// `output_dir` and `cache_dir` are written with forward slashes, `OpenUtau`'s
+    /// convention on every platform. A wave part path that cannot be expressed relative
+    /// to `new_root`, such as one on another drive, is kept absolute.
+    pub fn rebase_paths(&mut self, old_root: &Path, new_root: &Path) {
+        let old_root = absolute(old_root);
+        let new_root = lexical_normalize(&absolute(new_root));
+        for part in &mut self.wave_parts {
+            let audio = lexical_normalize(&old_root.join(forward_slashes(&part.relative_path)));
+            let rebased = relative_to(&audio, &new_root).unwrap_or(audio);
+            part.relative_path = forward_slashes(&rebased.to_string_lossy());
+        }
+        self.output_dir = forward_slashes(&self.output_dir);
+        self.cache_dir = forward_slashes(&self.cache_dir);
+    }
+
+    /// Returns whether two projects have the same musical content.
+    ///
+    /// Every field is compared except `comment`, `output_dir`, `cache_dir`, and
+    /// `ustx_version`, which do not affect how the project sounds.
+    #[must_use]
+    pub fn semantically_eq(&self, other: &Self) -> bool {
+        self.name == other.name
+            && self.resolution == other.resolution
+            && self.bpm == other.bpm
+            && self.beat_per_bar == other.beat_per_bar
+            && self.beat_unit == other.beat_unit
+            && self.expressions == other.expressions
+            && self.exp_selectors == other.exp_selectors
+            && self.exp_primary == other.exp_primary
+            && self.exp_secondary == other.exp_secondary
+            && self.key == other.key
+            && self.time_signatures == other.time_signatures
+            && self.tempos == other.tempos
+            && self.tracks == other.tracks
+            && self.voice_parts == other.voice_parts
+            && self.wave_parts == other.wave_parts
+    }
+
+    /// Declares a new expression, keyed by its `abbr`.
+    ///
+    /// Returns [`Error::InvalidExpression`] without modifying the project if an expression
+    /// with the same abbreviation already exists, if the descriptor fails
+    /// [`ExpressionDescriptor::validate`], or if a numerical descriptor's `default_value`
+    /// is outside `min..=max`.
+    pub fn add_expression(&mut self, descriptor: ExpressionDescriptor) -> Result<(), Error> {
+        let abbr = &descriptor.abbr;
+        if self.expressions.contains_key(abbr) {
+            return Err(Error::InvalidExpression(format!(
+                "`{abbr}` is already declared"
+            )));
+        }
+        descriptor.validate()?;
+        if descriptor.r#type == ExpressionType::Numerical
+            && !(descriptor.min <= descriptor.default_value
+                && descriptor.default_value <= descriptor.max)
+        {
+            return Err(Error::InvalidExpression(format!(
+                "`{abbr}` default {} is outside {}..={}",
+                descriptor.default_value, descriptor.min, descriptor.max
+            )));
+        }
+        self.expressions.insert(abbr.clone(), descriptor);
+        Ok(())
+    }
+
+    /// Renames the expression `old_abbr` to `new_abbr` throughout the project.
+    ///
+    /// The `expressions` key and the descriptor's `abbr` are renamed, along with every
+    /// track expression, note expression, curve, and `exp_selectors` entry that refers
+    /// to `old_abbr`. Returns the number of these references that were updated, not
+    /// counting the descriptor itself.
+    ///
+    /// Returns [`Error::InvalidExpression`] without modifying the project if `old_abbr`
+    /// is not declared or `new_abbr` already is. Renaming a declared expression to its
+    /// own name does nothing and returns 0.
+    pub fn rename_expression(&mut self, old_abbr: &str, new_abbr: &str) -> Result<usize, Error> {
+        if old_abbr == new_abbr && self.expressions.contains_key(old_abbr) {
+            return Ok(0);
+        }
+        if self.expressions.contains_key(new_abbr) {
+            return Err(Error::InvalidExpression(format!(
+                "`{new_abbr}` is already declared"
+            )));
+        }
+        let mut descriptor = self
+            .expressions
+            .remove(old_abbr)
+            .ok_or_else(|| Error::InvalidExpression(format!("`{old_abbr}` is not declared")))?;
+        new_abbr.clone_into(&mut descriptor.abbr);
+        self.expressions.insert(new_abbr.to_owned(), descriptor);
+
+        let mut updated = 0;
+        let mut rename = |abbr: &mut String| {
+            if abbr == old_abbr {
+                new_abbr.clone_into(abbr);
+                updated += 1;
+            }
+        };
+        for track in &mut self.tracks {
+            for expression in &mut track.track_expressions {
+                rename(&mut expression.abbr);
+            }
+        }
+        self.map_voice_parts(|part| {
+            for curve in &mut part.curves {
+                rename(&mut curve.abbr);
+            }
+        });
+        self.map_notes(|note| {
+            for expression in &mut note.phoneme_expressions {
+                rename(&mut expression.abbr);
+            }
+        });
+        for selector in &mut self.exp_selectors {
+            rename(selector);
+        }
+        Ok(updated)
+    }
+
+    /// Counts how many times each expression abbreviation is used by note expressions,
+    /// track expressions, and part curves.
+    #[must_use]
+    pub fn expression_usage(&self) -> BTreeMap<String, usize> {
+        let mut usage = BTreeMap::new();
+        let abbrs = self
+            .tracks
+            .iter()
+            .flat_map(|track| &track.track_expressions)
+            .map(|expression| &expression.abbr)
+            .chain(
+                self.voice_parts
+                    .iter()
+                    .flat_map(|part| &part.curves)
+                    .map(|curve| &curve.abbr),
+            )
+            .chain(
+                self.notes()
+                    .flat_map(|note| &note.phoneme_expressions)
+                    .map(|expression| &expression.abbr),
+            );
+        for abbr in abbrs {
+            *usage.entry(abbr.clone()).or_insert(0) += 1;
+        }
+        usage
+    }
+
+    /// Returns the declared expressions that are never used, as counted by
+    /// [`Project::expression_usage`], in abbreviation order.
+    #[must_use]
+    pub fn unused_expressions(&self) -> Vec<String> {
+        let usage = self.expression_usage();
+        self.expressions
+            .keys()
+            .filter(|abbr| !usage.contains_key(*abbr))
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the expressions that are used, as counted by
+    /// [`Project::expression_usage`], but not declared in `expressions`, in abbreviation
+    /// order.
+    #[must_use]
+    pub fn undeclared_used_expressions(&self) -> Vec<String> {
+        self.expression_usage()
+            .into_keys()
+            .filter(|abbr| !self.expressions.contains_key(abbr))
+            .collect()
+    }
+
+    /// Returns the standard `OpenUtau` expressions that the project does not declare, in
+    /// [`STANDARD_EXPRESSIONS`] order.
+    #[must_use]
+    pub fn missing_standard_expressions(&self) -> Vec<&'static str> {
+        STANDARD_EXPRESSIONS
+            .into_iter()
+            .filter(|abbr| !self.expressions.contains_key(*abbr))
+            .collect()
+    }
+
+    /// Declares every missing standard expression with `OpenUtau`'s default descriptor.
+    ///
+    /// Existing descriptors are left untouched, even if they differ from the defaults.
+    pub fn add_standard_expressions(&mut self) {
+        for abbr in self.missing_standard_expressions() {
+            if let Some(descriptor) = ExpressionDescriptor::standard(abbr) {
+                self.expressions.insert(String::from(abbr), descriptor);
+            }
+        }
+    }
+
+    /// Points the `index` of every track and note expression at its `abbr` in
+    /// `exp_selectors`.
+    ///
+    /// The abbreviation is treated as authoritative, so mismatched pairs are fixed by
+    /// [`Expression::sync_index`](crate::Expression::sync_index).
+    pub fn reconcile_expression_indexes(&mut self) {
+        let selectors = &self.exp_selectors;
+        let track_expressions = self
+            .tracks
+            .iter_mut()
+            .flat_map(|track| &mut track.track_expressions);
+        let note_expressions = self
+            .voice_parts
+            .iter_mut()
+            .flat_map(|part| &mut part.notes)
+            .flat_map(|note| &mut note.phoneme_expressions);
+        for expression in track_expressions.chain(note_expressions) {
+            expression.sync_index(selectors);
+        }
+    }
+
+    /// Resolves the value of the expression `abbr`.
+    ///
+    /// Returns `explicit` if present, otherwise the `default_value` of the project's
+    /// descriptor for `abbr`, or `None` if the project does not declare it.
+    #[must_use]
+    pub fn effective_expression(&self, abbr: &str, explicit: Option<f32>) -> Option<f32> {
+        explicit.or_else(|| {
+            self.expressions
+                .get(abbr)
+                .map(|descriptor| descriptor.default_value)
+        })
+    }
+
+    /// Returns the distinct renderer names used across all tracks.
+    #[must_use]
+    pub fn renderers(&self) -> BTreeSet<&str> {
+        self.tracks
+            .iter()
+            .filter_map(|track| track.renderer_settings.renderer.as_deref())
+            .collect()
+    }
+
+    /// Replaces the renderer `from` with `to` on every track, returning the number of
+    /// tracks changed.
+    pub fn set_renderer(&mut self, from: &str, to: &str) -> usize {
+        let mut changed = 0;
+        for track in &mut self.tracks {
+            if let Some(renderer) = &mut track.renderer_settings.renderer
+                && renderer == from
+            {
+                *renderer = String::from(to);
+                changed += 1;
+            }
+        }
+        changed
+    }
+
+    /// Returns the name of the project's key, such as `C` or `F#`.
+    ///
+    /// `key` counts semitones up from C and is wrapped into `0..12`, so out-of-range
+    /// values, including negative ones, still name a key.
+    #[inline]
+    #[must_use]
+    pub fn key_name(&self) -> String {
+        pitch::pitch_class_name(self.key).to_owned()
+    }
+
+    /// Sets `key` from a key name such as `C`, `f#` or `Bb`, normalized into `0..12`.
+    ///
+    /// The key is left unchanged if `name` cannot be parsed. See
+    /// [`pitch::parse_pitch_class_name`] for the accepted syntax.
+    #[inline]
+    pub fn set_key_by_name(&mut self, name: &str) -> Result<(), Error> {
+        self.key = pitch::parse_pitch_class_name(name)?;
+        Ok(())
+    }
+
+    /// Snaps every note's tone to the nearest tone of `scale` in the project's `key`.
+    ///
+    /// Notes already in the scale are left unchanged; ties round up.
+    pub fn snap_to_key(&mut self, scale: Scale) {
+        let key = self.key;
+        self.map_notes(|note| note.tone = scale.snap(key, note.tone));
+    }
+
+    /// Replaces `exp_selectors`, keeping `exp_primary` and `exp_secondary` pointed at the
+    /// same abbreviations.
+    ///
+    /// Each index is moved to the first position of its previously selected abbreviation
+    /// in `selectors`, or reset to 0 if that abbreviation is no longer listed.
+    pub fn set_exp_selectors(&mut self, selectors: Vec<String>) {
+        let remap = |index: i32| {
+            usize::try_from(index)
+                .ok()
+                .and_then(|index| self.exp_selectors.get(index))
+                .and_then(|abbr| selectors.iter().position(|selector| selector == abbr))
+                .and_then(|position| i32::try_from(position).ok())
+                .unwrap_or(0)
+        };
+        let primary = remap(self.exp_primary);
+        let secondary = remap(self.exp_secondary);
+        self.exp_selectors = selectors;
+        self.exp_primary = primary;
+        self.exp_secondary = secondary;
+    }
+
+    /// Puts the project into a canonical, self-consistent state.
+    ///
+    /// This touches exactly the following fields:
+    ///
+    /// - `tempos` is sorted by `position`, and seeded from `bpm` if empty.
+    /// - `time_signatures` is sorted by `bar_position`, and seeded from `beat_per_bar`
+    ///   and `beat_unit` if empty.
+    /// - `notes` in every voice part are sorted by [`Note::sort_key`].
+    /// - `exp_selectors` is padded to the default selector set, keeping existing entries.
+    /// - `exp_primary` and `exp_secondary` are clamped to valid `exp_selectors` indexes.
+    ///
+    /// All sorts are stable.
+    pub fn normalize(&mut self) {
+        if self.tempos.is_empty() {
+            self.tempos.push(self.initial_tempo());
+        }
+        self.tempos.sort_by_key(|tempo| tempo.position);
+        if self.time_signatures.is_empty() {
+            self.time_signatures.push(self.initial_time_signature());
+        }
+        self.time_signatures
+            .sort_by_key(|time_signature| time_signature.bar_position);
+        self.map_voice_parts(|part| part.notes.sort_by_key(Note::sort_key));
+        self.convert_pre_0_7();
+        let max_index =
+            i32::try_from(self.exp_selectors.len().saturating_sub(1)).unwrap_or(i32::MAX);
+        self.exp_primary = self.exp_primary.clamp(0, max_index);
+        self.exp_secondary = self.exp_secondary.clamp(0, max_index);
+    }
+
+    /// Returns a copy of the project with every ordered collection sorted by the keys
+    /// below, leaving `self` untouched.
+    ///
+    /// The sorts are stable rather than canonical: elements that tie on every listed key
+    /// keep their relative order. Two projects that differ only in the order of their
+    /// tempos, time signatures, parts, notes, or curves therefore produce equal copies,
+    /// and the same `content_hash`, unless the reordered elements tie on those keys.
+    /// Unlike [`Project::normalize`], nothing is seeded, padded, or clamped.
+    ///
+    /// - `tempos` are sorted by position, then bpm.
+    /// - `time_signatures` are sorted by bar position, then beats per bar and beat unit.
+    /// - voice and wave parts are sorted by track number, position, then name.
+    /// - `notes` are sorted by [`Note::sort_key`], then duration and lyric.
+    /// - `curves` are sorted by abbreviation.
+    #[must_use]
+    pub fn sorted_clone(&self) -> Self {
+        let mut project = self.clone();
+        project
+            .tempos
+            .sort_by(|a, b| a.position.cmp(&b.position).then(a.bpm.total_cmp(&b.bpm)));
+        project.time_signatures.sort_by_key(|time_signature| {
+            (
+                time_signature.bar_position,
+                time_signature.beat_per_bar,
+                time_signature.beat_unit,
+            )
+        });
+        project.voice_parts.sort_by(|a, b| {
+            (a.track_no, a.position, &a.name).cmp(&(b.track_no, b.position, &b.name))
+        });
+        project.wave_parts.sort_by(|a, b| {
+            (a.track_no, a.position, &a.name).cmp(&(b.track_no, b.position, &b.name))
+        });
+        project.map_voice_parts(|part| {
+            part.notes.sort_by(|a, b| {
+                (a.sort_key(), a.duration, &a.lyric).cmp(&(b.sort_key(), b.duration, &b.lyric))
+            });
+            part.curves.sort_by(|a, b| a.abbr.cmp(&b.abbr));
+        });
+        project
+    }
+
+    /// Returns the project with `ustx_version` set to `version`, without converting it.
+    #[inline]
+    #[must_use]
+    pub const fn with_version(mut self, version: Version) -> Self {
+        self.ustx_version = Some(version);
+        self
+    }
 
     /// Converts the project to the specified `target` version.
     pub fn convert_to(&mut self, target: Version) -> Result<(), Error> {
@@ -122,7 +839,10 @@ impl Project {
             return Err(Error::unsupported_version(detected.to_string()));
         }
         if detected >= target {
-            self.ustx_version = Some(target);
+            // Keep the original spelling of the version when it is not changing.
+            if detected > target || self.ustx_version.is_none() {
+                self.ustx_version = Some(target);
+            }
             return Ok(());
         }
 
@@ -142,15 +862,44 @@ impl Project {
         self.ustx_version = Some(target);
         Ok(())
     }
+
+    /// Consumes the project and returns it converted to [`CURRENT_VERSION`].
+    ///
+    /// This is the in-memory counterpart of `Project::from_yaml_str_with_compat` for
+    /// projects that are already deserialized. A `resolution` that is not positive is
+    /// rejected with [`Error::InvalidResolution`]; otherwise it fails the same way as
+    /// [`Project::convert_to`].
+    #[inline]
+    pub fn upgraded(mut self) -> Result<Self, Error> {
+        if self.resolution <= 0 {
+            return Err(Error::InvalidResolution(self.resolution));
+        }
+        self.convert_to(CURRENT_VERSION)?;
+        Ok(self)
+    }
 }
 
+impl Index<usize> for Project {
+    type Output = Track;
+
+    #[inline]
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.tracks[index]
+    }
+}
+
+/// Formats the project as YAML exactly as it is, without upgrading its version.
+///
+/// Use [`Project::to_yaml_string_with_compat`] to print the upgraded form instead.
+#[cfg(feature = "yaml")]
 impl fmt::Display for Project {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        self.to_yaml_string_with_compat()
+        self.to_yaml_string()
             .map_or(Err(fmt::Error), |text| f.write_str(&text))
     }
 }
 
+#[cfg(feature = "yaml")]
 impl FromStr for Project {
     type Err = Error;
 
@@ -160,6 +909,92 @@ impl FromStr for Project {
     }
 }
 
+/// Deserializes a `Project` from an already parsed YAML value, like
+/// [`Project::from_yaml_str`].
+#[cfg(feature = "yaml")]
+impl TryFrom<Value> for Project {
+    type Error = Error;
+
+    #[inline]
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        Self::deserialize(value).map_err(Error::from)
+    }
+}
+
+/// Serializes a `Project` into a YAML value, like [`Project::to_yaml_string`].
+#[cfg(feature = "yaml")]
+impl TryFrom<&Project> for Value {
+    type Error = Error;
+
+    #[inline]
+    fn try_from(project: &Project) -> Result<Self, Self::Error> {
+        serde_yaml::to_value(project).map_err(Error::from)
+    }
+}
+
+/// Replaces Windows path separators with forward slashes.
+fn forward_slashes(path: &str) -> String {
+    path.replace('\\', "/")
+}
+
+/// Makes `path` absolute by joining it to the current directory if it is relative.
+///
+/// The path is returned unchanged if the current directory cannot be determined.
+fn absolute(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Resolves `.` and `..` components of `path` without accessing the file system.
+fn lexical_normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match normalized.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    normalized.pop();
+                }
+                Some(Component::RootDir | Component::Prefix(_)) => {}
+                _ => normalized.push(component),
+            },
+            _ => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// Returns `path` relative to `base`, or `None` if they share no root or `base` goes
+/// above their common ancestor with a `..` that cannot be undone.
+///
+/// Both paths must already be lexically normalized.
+fn relative_to(path: &Path, base: &Path) -> Option<PathBuf> {
+    let mut path_components = path.components().peekable();
+    let mut base_components = base.components().peekable();
+    if path.has_root() != base.has_root() {
+        return None;
+    }
+    while let (Some(a), Some(b)) = (path_components.peek(), base_components.peek()) {
+        if a != b {
+            break;
+        }
+        path_components.next();
+        base_components.next();
+    }
+    if base_components.clone().any(|component| {
+        matches!(
+            component,
+            Component::Prefix(_) | Component::RootDir | Component::ParentDir
+        )
+    }) {
+        return None;
+    }
+    let mut relative = base_components
+        .map(|_| Component::ParentDir)
+        .collect::<PathBuf>();
+    relative.extend(path_components);
+    Some(relative)
+}
+
 #[inline]
 fn default_project_name() -> String {
     String::from("New Project")
@@ -231,6 +1066,44 @@ fn default_tracks() -> Vec<Track> {
     vec![Track::default()]
 }
 
+/// A 64-bit FNV-1a hasher, used because `std`'s default hasher is not stable across
+/// releases.
+#[cfg(feature = "yaml")]
+struct Fnv1a(u64);
+
+#[cfg(feature = "yaml")]
+impl Fnv1a {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    #[inline]
+    const fn new() -> Self {
+        Self(Self::OFFSET_BASIS)
+    }
+
+    #[inline]
+    const fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+#[cfg(feature = "yaml")]
+impl std::io::Write for Fnv1a {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        for &byte in buf {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(Self::PRIME);
+        }
+        Ok(buf.len())
+    }
+
+    #[inline]
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
 const VERSION_0_4: Version = Version::new(0, 4, 0);
 const VERSION_0_5: Version = Version::new(0, 5, 0);
 const VERSION_0_6: Version = Version::new(0, 6, 0);
@@ -239,9 +1112,6 @@ const VERSION_0_7: Version = Version::new(0, 7, 0);
 const OLD_ACCENT_ABBR: &str = "acc";
 const NEW_ACCENT_ABBR: &str = "atk";
 const NEW_ACCENT_NAME: &str = "attack";
-const DEFAULT_SELECTORS: [&str; 10] = [
-    "dyn", "pitd", "clr", "eng", "vel", "vol", "atk", "dec", "gen", "bre",
-];
 
 impl Project {
     fn convert_pre_0_4(&mut self) {
@@ -260,28 +1130,31 @@ impl Project {
         self.expressions
             .insert(String::from(NEW_ACCENT_ABBR), descriptor);
 
-        for part in &mut self.voice_parts {
-            for note in &mut part.notes {
-                for expression in &mut note.phoneme_expressions {
-                    if expression.abbr == OLD_ACCENT_ABBR {
-                        expression.abbr = String::from(NEW_ACCENT_ABBR);
-                    }
+        self.map_notes(|note| {
+            for expression in &mut note.phoneme_expressions {
+                if expression.abbr == OLD_ACCENT_ABBR {
+                    expression.abbr = String::from(NEW_ACCENT_ABBR);
                 }
             }
-        }
+        });
     }
 
     fn convert_pre_0_5(&mut self) {
-        for part in &mut self.voice_parts {
-            for note in &mut part.notes {
-                if note.lyric.starts_with("...") {
-                    note.lyric = note.lyric.replace("...", "+");
-                }
+        self.map_notes(|note| {
+            if note.lyric.starts_with(LEGACY_CONNECTOR_PREFIX) {
+                note.lyric = note.lyric.replace(LEGACY_CONNECTOR_PREFIX, "+");
             }
-        }
+        });
     }
 
     fn convert_pre_0_6(&mut self) {
+        self.time_signatures = vec![self.initial_time_signature()];
+        self.tempos = vec![self.initial_tempo()];
+    }
+
+    /// Returns a time signature at bar 0 derived from the legacy `beat_per_bar` and
+    /// `beat_unit` fields.
+    const fn initial_time_signature(&self) -> TimeSignature {
         let beat_per_bar = if self.beat_per_bar > 0 {
             self.beat_per_bar
         } else {
@@ -292,21 +1165,24 @@ impl Project {
         } else {
             4
         };
-        let bpm = if self.bpm > 0.0 { self.bpm } else { 120.0 };
-
-        self.time_signatures = vec![TimeSignature {
+        TimeSignature {
             bar_position: 0,
             beat_per_bar,
             beat_unit,
-        }];
-        self.tempos = vec![Tempo { position: 0, bpm }];
+        }
+    }
+
+    /// Returns a tempo at tick 0 derived from the legacy `bpm` field.
+    const fn initial_tempo(&self) -> Tempo {
+        let bpm = if self.bpm > 0.0 { self.bpm } else { 120.0 };
+        Tempo { position: 0, bpm }
     }
 
     fn convert_pre_0_7(&mut self) {
-        if self.exp_selectors.len() >= DEFAULT_SELECTORS.len() {
+        if self.exp_selectors.len() >= STANDARD_EXPRESSIONS.len() {
             return;
         }
-        let mut selectors = DEFAULT_SELECTORS
+        let mut selectors = STANDARD_EXPRESSIONS
             .iter()
             .map(|&value| String::from(value))
             .collect::<Vec<_>>();
@@ -320,3 +1196,49 @@ impl Project {
         self.exp_selectors = selectors;
     }
 }
+
+/// Removes the elements of `items` that do not deserialize as `T`, recording an error for
+/// each.
+#[cfg(feature = "yaml")]
+fn retain_valid<T: for<'de> Deserialize<'de>>(items: &mut Vec<Value>, errors: &mut Vec<Error>) {
+    items.retain(|item| match T::deserialize(item) {
+        Ok(_) => true,
+        Err(err) => {
+            errors.push(err.into());
+            false
+        }
+    });
+}
+
+/// Strips a leading UTF-8 byte order mark from `input`.
+#[cfg(feature = "yaml")]
+pub(crate) fn strip_bom(input: &str) -> &str {
+    input.strip_prefix('\u{feff}').unwrap_or(input)
+}
+
+/// Rounds every float in `value` to the precision given by `factor` (`10^decimals`).
+#[cfg(feature = "yaml")]
+fn round_floats(value: &mut Value, factor: f64) {
+    match value {
+        Value::Number(number) if number.is_f64() => {
+            if let Some(float) = number.as_f64() {
+                let rounded = (float * factor).round() / factor;
+                if rounded.is_finite() {
+                    *number = rounded.into();
+                }
+            }
+        }
+        Value::Sequence(sequence) => {
+            for item in sequence {
+                round_floats(item, factor);
+            }
+        }
+        Value::Mapping(mapping) => {
+            for (_, item) in mapping.iter_mut() {
+                round_floats(item, factor);
+            }
+        }
+        Value::Tagged(tagged) => round_floats(&mut tagged.value, factor),
+        _ => {}
+    }
+}
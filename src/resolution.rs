@@ -0,0 +1,104 @@
+use crate::project::Project;
+
+/// Represents a tick value that could not be rescaled exactly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LossyTick {
+    /// The kind of value that was rounded, such as `"note.position"`.
+    pub field: &'static str,
+    /// The value before rescaling.
+    pub original: i32,
+    /// The rescaled value after rounding to the nearest tick.
+    pub rounded: i32,
+}
+
+/// Represents the outcome of [`Project::set_resolution`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResolutionReport {
+    /// The tick values that lost precision, in traversal order.
+    pub lossy: Vec<LossyTick>,
+}
+
+impl ResolutionReport {
+    /// Returns whether every value was rescaled exactly.
+    #[inline]
+    #[must_use]
+    pub const fn is_exact(&self) -> bool {
+        self.lossy.is_empty()
+    }
+}
+
+struct Rescaler {
+    from: i64,
+    to: i64,
+    report: ResolutionReport,
+}
+
+impl Rescaler {
+    fn tick(&mut self, field: &'static str, value: &mut i32) {
+        let scaled = i64::from(*value) * self.to;
+        let rounded = (2 * scaled + self.from).div_euclid(2 * self.from);
+        let rounded =
+            i32::try_from(rounded).unwrap_or(if rounded < 0 { i32::MIN } else { i32::MAX });
+        if scaled % self.from != 0 {
+            self.report.lossy.push(LossyTick {
+                field,
+                original: *value,
+                rounded,
+            });
+        }
+        *value = rounded;
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn ratio(&self) -> f32 {
+        self.to as f32 / self.from as f32
+    }
+}
+
+impl Project {
+    /// Changes the project's resolution, rescaling every tick-based value to match.
+    ///
+    /// Part positions, note positions and durations, curve `xs`, and tempo positions
+    /// are multiplied by `new_resolution / resolution` and rounded to the nearest tick,
+    /// with halves rounding up. Pitch point `x` values are scaled without rounding.
+    /// Time signature `bar_position`s are in bars and are left unchanged.
+    ///
+    /// Returns a report listing every value that had to be rounded. If either the old or
+    /// new resolution is not positive, the project is left unchanged.
+    pub fn set_resolution(&mut self, new_resolution: i32) -> ResolutionReport {
+        if self.resolution <= 0 || new_resolution <= 0 || self.resolution == new_resolution {
+            return ResolutionReport::default();
+        }
+        let mut rescaler = Rescaler {
+            from: i64::from(self.resolution),
+            to: i64::from(new_resolution),
+            report: ResolutionReport::default(),
+        };
+        let ratio = rescaler.ratio();
+
+        for tempo in &mut self.tempos {
+            rescaler.tick("tempo.position", &mut tempo.position);
+        }
+        for part in &mut self.voice_parts {
+            rescaler.tick("voice_part.position", &mut part.position);
+            for note in &mut part.notes {
+                rescaler.tick("note.position", &mut note.position);
+                rescaler.tick("note.duration", &mut note.duration);
+                for point in &mut note.pitch.data {
+                    point.x *= ratio;
+                }
+            }
+            for curve in &mut part.curves {
+                for x in &mut curve.xs {
+                    rescaler.tick("curve.x", x);
+                }
+            }
+        }
+        for part in &mut self.wave_parts {
+            rescaler.tick("wave_part.position", &mut part.position);
+        }
+
+        self.resolution = new_resolution;
+        rescaler.report
+    }
+}
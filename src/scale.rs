@@ -0,0 +1,47 @@
+/// Represents a musical scale used to constrain note tones to a key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Scale {
+    /// The major (Ionian) scale.
+    #[default]
+    Major,
+    /// The natural minor (Aeolian) scale.
+    Minor,
+    /// The chromatic scale, which contains every pitch class.
+    Chromatic,
+}
+
+impl Scale {
+    /// Returns the pitch classes of the scale, as semitone offsets from the tonic.
+    #[inline]
+    #[must_use]
+    pub const fn intervals(self) -> &'static [i32] {
+        match self {
+            Self::Major => &[0, 2, 4, 5, 7, 9, 11],
+            Self::Minor => &[0, 2, 3, 5, 7, 8, 10],
+            Self::Chromatic => &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11],
+        }
+    }
+
+    /// Returns whether `tone` belongs to the scale rooted at `key`.
+    #[inline]
+    #[must_use]
+    pub fn contains(self, key: i32, tone: i32) -> bool {
+        let offset = (i64::from(tone) - i64::from(key)).rem_euclid(12);
+        self.intervals()
+            .iter()
+            .any(|&interval| i64::from(interval) == offset)
+    }
+
+    /// Snaps `tone` to the nearest tone of the scale rooted at `key`.
+    ///
+    /// Tones already in the scale are returned unchanged; ties round up. Candidates
+    /// outside the range of `i32` are skipped.
+    #[must_use]
+    pub fn snap(self, key: i32, tone: i32) -> i32 {
+        (0..12)
+            .flat_map(|distance| [i64::from(tone) + distance, i64::from(tone) - distance])
+            .filter_map(|candidate| i32::try_from(candidate).ok())
+            .find(|&candidate| self.contains(key, candidate))
+            .unwrap_or(tone)
+    }
+}
@@ -0,0 +1,129 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::project::Project;
+use crate::time::Ticks;
+
+/// Represents a summary of a project's contents.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct ProjectStats {
+    /// The total number of notes across all voice parts.
+    pub note_count: usize,
+    /// The number of voice parts.
+    pub voice_part_count: usize,
+    /// The number of wave parts.
+    pub wave_part_count: usize,
+    /// The number of notes on each track, indexed like `Project::tracks`.
+    pub track_note_counts: Vec<usize>,
+    /// The number of distinct lyrics.
+    pub distinct_lyrics: usize,
+    /// The lowest note tone, as a MIDI note number.
+    pub min_tone: Option<i32>,
+    /// The highest note tone, as a MIDI note number.
+    pub max_tone: Option<i32>,
+    /// The musical length of the project in ticks, which is the latest voice part end.
+    pub length_ticks: i32,
+}
+
+impl Project {
+    /// Computes summary statistics for the project.
+    ///
+    /// Notes in parts whose `track_no` does not refer to an existing track are counted in
+    /// `note_count` but not in `track_note_counts`.
+    #[must_use]
+    pub fn statistics(&self) -> ProjectStats {
+        let mut track_note_counts = vec![0; self.tracks.len()];
+        for part in &self.voice_parts {
+            if let Some(count) = usize::try_from(part.track_no)
+                .ok()
+                .and_then(|index| track_note_counts.get_mut(index))
+            {
+                *count += part.notes.len();
+            }
+        }
+        ProjectStats {
+            note_count: self.notes().count(),
+            voice_part_count: self.voice_parts.len(),
+            wave_part_count: self.wave_parts.len(),
+            track_note_counts,
+            distinct_lyrics: self
+                .notes()
+                .map(|note| note.lyric.as_str())
+                .collect::<BTreeSet<_>>()
+                .len(),
+            min_tone: self.notes().map(|note| note.tone).min(),
+            max_tone: self.notes().map(|note| note.tone).max(),
+            length_ticks: self.voice_length_ticks().0,
+        }
+    }
+
+    /// Counts how many notes use each lyric, across all voice parts.
+    ///
+    /// Connector and rest lyrics are included; see
+    /// [`Project::lyric_frequencies_filtered`] to leave them out.
+    #[inline]
+    #[must_use]
+    pub fn lyric_frequencies(&self) -> BTreeMap<String, usize> {
+        self.lyric_frequencies_filtered(true)
+    }
+
+    /// Counts how many notes use each lyric, across all voice parts.
+    ///
+    /// If `include_special` is `false`, connector notes ([`Note::is_connector`]) and rest
+    /// notes ([`Note::is_rest`]) are not counted, leaving only the lyrics a voicebank has
+    /// to sing.
+    ///
+    /// [`Note::is_connector`]: crate::Note::is_connector
+    /// [`Note::is_rest`]: crate::Note::is_rest
+    #[must_use]
+    pub fn lyric_frequencies_filtered(&self, include_special: bool) -> BTreeMap<String, usize> {
+        let mut frequencies = BTreeMap::new();
+        for note in self.notes() {
+            if include_special || !(note.is_connector() || note.is_rest()) {
+                *frequencies.entry(note.lyric.clone()).or_insert(0) += 1;
+            }
+        }
+        frequencies
+    }
+
+    /// Returns every distinct lyric across all voice parts, including connector and rest
+    /// lyrics.
+    #[must_use]
+    pub fn unique_lyrics(&self) -> BTreeSet<String> {
+        self.notes().map(|note| note.lyric.clone()).collect()
+    }
+
+    /// Returns a one-line, human-readable summary of the project for logging.
+    ///
+    /// For example: `Demo — 3 tracks, 2 parts, 412 notes, 120 BPM, 4/4, ustx 0.7`. The
+    /// part count includes wave parts, the tempo and time signature are those in effect
+    /// at the start, and the version is left out if the project has none.
+    #[must_use]
+    pub fn summary_line(&self) -> String {
+        let time_signature = self.time_signature_at(0);
+        let version = self
+            .ustx_version
+            .as_ref()
+            .map_or_else(String::new, |version| format!(", ustx {version}"));
+        format!(
+            "{} — {}, {}, {}, {} BPM, {}/{}{version}",
+            self.name,
+            plural(self.tracks.len(), "track"),
+            plural(self.voice_parts.len() + self.wave_parts.len(), "part"),
+            plural(self.notes().count(), "note"),
+            self.tempo_at_tick(Ticks(0)),
+            time_signature.beat_per_bar,
+            time_signature.beat_unit,
+        )
+    }
+}
+
+/// Formats `count` followed by `noun`, adding an `s` unless `count` is 1.
+fn plural(count: usize, noun: &str) -> String {
+    if count == 1 {
+        format!("{count} {noun}")
+    } else {
+        format!("{count} {noun}s")
+    }
+}
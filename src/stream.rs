@@ -0,0 +1,193 @@
+use serde::de::{
+    DeserializeOwned, DeserializeSeed, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor,
+};
+use std::fmt::{self, Formatter};
+use std::marker::PhantomData;
+
+use crate::error::Error;
+use crate::note::Note;
+use crate::project::{Project, strip_bom};
+
+impl Project {
+    /// Counts the notes in every voice part of a YAML document without building the
+    /// project.
+    ///
+    /// See [`Project::for_each_note_in_yaml`].
+    #[inline]
+    pub fn count_notes_from_yaml(input: &str) -> Result<usize, Error> {
+        let mut count = 0;
+        Self::for_each_note_in_yaml(input, |_| count += 1)?;
+        Ok(count)
+    }
+
+    /// Calls `f` on every note in every voice part of a YAML document, in storage order.
+    ///
+    /// Notes are deserialized one at a time and dropped after `f` returns, and every
+    /// other part of the document is skipped, so memory use does not grow with the
+    /// number of notes. This suits analysis tools that do not need the whole project.
+    pub fn for_each_note_in_yaml<F: FnMut(&Note)>(input: &str, f: F) -> Result<(), Error> {
+        stream_parts(input, &mut NoteCallback(f))
+    }
+}
+
+/// Receives the parts and notes of a YAML document from [`stream_parts`].
+pub trait PartSink {
+    /// The type each note is deserialized as. Use [`IgnoredAny`] to skip note contents.
+    type Note: DeserializeOwned;
+
+    /// Called after each voice part has been visited.
+    fn voice_part(&mut self) {}
+
+    /// Called for each wave part, whose contents are skipped.
+    fn wave_part(&mut self) {}
+
+    /// Called for each note, in storage order.
+    fn note(&mut self, note: Self::Note);
+}
+
+/// Visits the `voice_parts` and `wave_parts` of the first YAML document in `input`,
+/// passing them to `sink` one at a time. Everything else in the document is skipped.
+pub fn stream_parts<S: PartSink>(input: &str, sink: &mut S) -> Result<(), Error> {
+    let mut documents = serde_yaml::Deserializer::from_str(strip_bom(input));
+    let document = documents.next().ok_or(Error::MissingDocument)?;
+    Document(sink).deserialize(document)?;
+    Ok(())
+}
+
+/// Passes every note to a callback.
+struct NoteCallback<F>(F);
+
+impl<F: FnMut(&Note)> PartSink for NoteCallback<F> {
+    type Note = Note;
+
+    fn note(&mut self, note: Note) {
+        (self.0)(&note);
+    }
+}
+
+/// Visits the root mapping, streaming its `voice_parts` and `wave_parts`.
+struct Document<'s, S>(&'s mut S);
+
+/// Visits a `voice_parts` or `wave_parts` sequence.
+struct Parts<'s, S> {
+    sink: &'s mut S,
+    voice: bool,
+}
+
+/// Visits a voice part mapping, streaming its `notes`.
+struct Part<'s, S>(&'s mut S);
+
+/// Visits a `notes` sequence, passing each note to the sink.
+struct Notes<'s, S>(&'s mut S);
+
+/// Implements `DeserializeSeed` for a visitor by deserializing it as `$kind`.
+macro_rules! seed {
+    ($visitor:ident, $kind:ident) => {
+        impl<'de, S: PartSink> DeserializeSeed<'de> for $visitor<'_, S> {
+            type Value = ();
+
+            fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+                deserializer.$kind(self)
+            }
+        }
+    };
+}
+
+seed!(Document, deserialize_map);
+seed!(Parts, deserialize_seq);
+seed!(Part, deserialize_map);
+seed!(Notes, deserialize_seq);
+
+impl<'de, S: PartSink> Visitor<'de> for Document<'_, S> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("a project mapping")
+    }
+
+    fn visit_unit<E>(self) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        while let Some(key) = map.next_key::<String>()? {
+            let voice = match key.as_str() {
+                "voice_parts" => true,
+                "wave_parts" => false,
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                    continue;
+                }
+            };
+            map.next_value_seed(Parts {
+                sink: &mut *self.0,
+                voice,
+            })?;
+        }
+        Ok(())
+    }
+}
+
+impl<'de, S: PartSink> Visitor<'de> for Parts<'_, S> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("a sequence of parts")
+    }
+
+    fn visit_unit<E>(self) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        let sink = self.sink;
+        if self.voice {
+            while seq.next_element_seed(Part(&mut *sink))?.is_some() {
+                sink.voice_part();
+            }
+        } else {
+            while seq.next_element::<IgnoredAny>()?.is_some() {
+                sink.wave_part();
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'de, S: PartSink> Visitor<'de> for Part<'_, S> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("a voice part mapping")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        while let Some(key) = map.next_key::<String>()? {
+            if key == "notes" {
+                map.next_value_seed(Notes(&mut *self.0))?;
+            } else {
+                map.next_value::<IgnoredAny>()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'de, S: PartSink> Visitor<'de> for Notes<'_, S> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("a sequence of notes")
+    }
+
+    fn visit_unit<E>(self) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        while let Some(note) = seq.next_element_seed(PhantomData::<S::Note>)? {
+            self.0.note(note);
+        }
+        Ok(())
+    }
+}
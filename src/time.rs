@@ -1,7 +1,74 @@
+//! Tempo and time signature maps, and the units of time computed from them.
+//!
+//! Computed-value APIs such as [`Project::tick_to_ms`] take and return [`Ticks`] and
+//! [`Millis`] so that ticks and milliseconds cannot be mixed up. Serialized fields stay
+//! plain numbers.
+
 use serde::{Deserialize, Serialize};
 
+use crate::project::Project;
+use std::mem;
+
+/// A position or length on the timeline, in ticks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Ticks(pub i32);
+
+/// A position or length on the timeline in ticks, keeping the fraction that
+/// [`Project::ms_to_tick`] produces.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+pub struct FractionalTicks(pub f64);
+
+impl FractionalTicks {
+    /// Rounds to the nearest whole tick, clamping to the `i32` range.
+    #[inline]
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub const fn round(self) -> Ticks {
+        Ticks(self.0.round() as i32)
+    }
+}
+
+impl From<Ticks> for FractionalTicks {
+    #[inline]
+    fn from(value: Ticks) -> Self {
+        Self(f64::from(value.0))
+    }
+}
+
+/// A point in time or duration, in milliseconds.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+pub struct Millis(pub f64);
+
+impl From<i32> for Ticks {
+    #[inline]
+    fn from(value: i32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<Ticks> for i32 {
+    #[inline]
+    fn from(value: Ticks) -> Self {
+        value.0
+    }
+}
+
+impl From<f64> for Millis {
+    #[inline]
+    fn from(value: f64) -> Self {
+        Self(value)
+    }
+}
+
+impl From<Millis> for f64 {
+    #[inline]
+    fn from(value: Millis) -> Self {
+        value.0
+    }
+}
+
 /// Represents a tempo change in a project.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub struct Tempo {
     /// The position of the tempo change in ticks.
@@ -23,7 +90,7 @@ impl Default for Tempo {
 }
 
 /// Represents a time signature change in a project.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub struct TimeSignature {
     /// The bar position of the time signature change.
@@ -48,6 +115,383 @@ impl Default for TimeSignature {
     }
 }
 
+impl TimeSignature {
+    /// Returns the length of one bar in ticks at `resolution` ticks per quarter note, or 0
+    /// if `beat_unit` is not positive. Lengths outside the `i32` range are clamped to it.
+    #[inline]
+    #[must_use]
+    pub const fn ticks_per_bar(&self, resolution: i32) -> i32 {
+        if self.beat_unit > 0 {
+            saturate(resolution as i64 * 4 * self.beat_per_bar as i64 / self.beat_unit as i64)
+        } else {
+            0
+        }
+    }
+}
+
+impl Project {
+    /// Inserts `tempo` in `position` order, replacing any tempo at the same position.
+    ///
+    /// `tempos` is assumed to already be sorted by `position`.
+    pub fn insert_tempo(&mut self, tempo: Tempo) {
+        match self
+            .tempos
+            .binary_search_by_key(&tempo.position, |existing| existing.position)
+        {
+            Ok(index) => self.tempos[index] = tempo,
+            Err(index) => self.tempos.insert(index, tempo),
+        }
+    }
+
+    /// Inserts `time_signature` in `bar_position` order, replacing any time signature at
+    /// the same bar.
+    ///
+    /// `time_signatures` is assumed to already be sorted by `bar_position`.
+    pub fn insert_time_signature(&mut self, time_signature: TimeSignature) {
+        match self
+            .time_signatures
+            .binary_search_by_key(&time_signature.bar_position, |existing| {
+                existing.bar_position
+            }) {
+            Ok(index) => self.time_signatures[index] = time_signature,
+            Err(index) => self.time_signatures.insert(index, time_signature),
+        }
+    }
+
+    /// Sorts the tempo map and removes entries that share a position, keeping the last
+    /// one for each: tempos by `position` and time signatures by `bar_position`.
+    ///
+    /// Returns the number of entries removed. This repairs the
+    /// [`ValidationIssue::DuplicateTempoPosition`] and
+    /// [`ValidationIssue::DuplicateTimeSignature`] issues.
+    ///
+    /// [`ValidationIssue::DuplicateTempoPosition`]: crate::ValidationIssue::DuplicateTempoPosition
+    /// [`ValidationIssue::DuplicateTimeSignature`]: crate::ValidationIssue::DuplicateTimeSignature
+    pub fn dedup_tempos(&mut self) -> usize {
+        let before = self.tempos.len() + self.time_signatures.len();
+        self.tempos.sort_by_key(|tempo| tempo.position);
+        self.tempos.dedup_by(|later, kept| {
+            let duplicate = later.position == kept.position;
+            if duplicate {
+                mem::swap(later, kept);
+            }
+            duplicate
+        });
+        self.time_signatures
+            .sort_by_key(|time_signature| time_signature.bar_position);
+        self.time_signatures.dedup_by(|later, kept| {
+            let duplicate = later.bar_position == kept.bar_position;
+            if duplicate {
+                mem::swap(later, kept);
+            }
+            duplicate
+        });
+        before - self.tempos.len() - self.time_signatures.len()
+    }
+
+    /// Moves the whole song by `delta_ticks`, returning how many positions had to be
+    /// clamped at 0.
+    ///
+    /// Every voice and wave part moves by `delta_ticks`; notes stay where they are
+    /// within their parts. Tempos move too, except one at tick 0, which keeps governing
+    /// the start of the song. Time signatures move by the same number of bars when
+    /// `delta_ticks` is a whole number of bars of the initial time signature, again
+    /// except one at bar 0; otherwise they are left unchanged.
+    ///
+    /// With a negative `delta_ticks`, anything that would move before the start is
+    /// placed at 0 instead and counted in the result. When several tempos or time
+    /// signatures end up at 0 this way, the last one in storage order wins and the
+    /// superseded ones are removed, as with [`Project::dedup_tempos`].
+    pub fn shift_time(&mut self, delta_ticks: i32) -> usize {
+        let mut clamped = 0;
+        let mut shift = |position: &mut i32, delta: i32| {
+            let shifted = position.saturating_add(delta);
+            if shifted < 0 {
+                clamped += 1;
+            }
+            *position = shifted.max(0);
+        };
+        for part in &mut self.voice_parts {
+            shift(&mut part.position, delta_ticks);
+        }
+        for part in &mut self.wave_parts {
+            shift(&mut part.position, delta_ticks);
+        }
+        for tempo in &mut self.tempos {
+            if tempo.position != 0 {
+                shift(&mut tempo.position, delta_ticks);
+            }
+        }
+        let ticks_per_bar = self.time_signature_at(0).ticks_per_bar(self.resolution);
+        if ticks_per_bar > 0 && delta_ticks % ticks_per_bar == 0 {
+            let delta_bars = delta_ticks / ticks_per_bar;
+            for time_signature in &mut self.time_signatures {
+                if time_signature.bar_position != 0 {
+                    shift(&mut time_signature.bar_position, delta_bars);
+                }
+            }
+        }
+        keep_last_at_start(&mut self.tempos, |tempo| tempo.position);
+        keep_last_at_start(&mut self.time_signatures, |time_signature| {
+            time_signature.bar_position
+        });
+        clamped
+    }
+
+    /// Returns the tempo in effect at `tick`, in beats per minute.
+    ///
+    /// This is the last tempo at or before `tick`. Ticks before the first tempo use the
+    /// first tempo, and a project without tempos falls back to `bpm`.
+    #[must_use]
+    pub fn tempo_at_tick(&self, tick: Ticks) -> f64 {
+        let Ticks(tick) = tick;
+        self.tempos
+            .iter()
+            .take_while(|tempo| tempo.position <= tick)
+            .last()
+            .or_else(|| self.tempos.first())
+            .map_or(self.bpm, |tempo| tempo.bpm)
+    }
+
+    /// Returns the length of the project in ticks, which is the latest end of any voice
+    /// or wave part.
+    ///
+    /// Voice parts end at their last note, and wave parts after their
+    /// [`WavePart::duration_ticks`](crate::WavePart::duration_ticks). An empty project
+    /// has length 0. Ends past `i32::MAX` are clamped to it.
+    #[must_use]
+    pub fn length_ticks(&self) -> Ticks {
+        self.wave_parts
+            .iter()
+            .map(|part| Ticks(part.position.saturating_add(part.duration_ticks(self).0)))
+            .fold(self.voice_length_ticks(), Ticks::max)
+    }
+
+    /// Returns the length of the project in milliseconds, converting
+    /// [`Project::length_ticks`] through the tempo map.
+    #[must_use]
+    pub fn length_ms(&self) -> Millis {
+        self.tick_to_ms(self.length_ticks())
+    }
+
+    /// Returns the latest note end across all voice parts, or 0 if there are no notes.
+    pub(crate) fn voice_length_ticks(&self) -> Ticks {
+        self.voice_parts
+            .iter()
+            .filter_map(|part| {
+                part.tick_bounds()
+                    .map(|(_, end)| Ticks(part.position.saturating_add(end)))
+            })
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Returns `(part_index, start_tick, end_tick)` for every voice part with notes, where
+    /// the ticks are the part's [`VoicePart::tick_bounds`] placed on the project timeline.
+    ///
+    /// Parts without notes are skipped.
+    ///
+    /// [`VoicePart::tick_bounds`]: crate::VoicePart::tick_bounds
+    #[must_use]
+    pub fn part_bounds(&self) -> Vec<(usize, i32, i32)> {
+        self.voice_parts
+            .iter()
+            .enumerate()
+            .filter_map(|(index, part)| {
+                part.tick_bounds().map(|(start, end)| {
+                    (
+                        index,
+                        part.position.saturating_add(start),
+                        part.position.saturating_add(end),
+                    )
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the index pairs of voice parts on the same track whose
+    /// [`Project::part_bounds`] intersect.
+    ///
+    /// Parts that merely touch, with one ending where the next starts, do not overlap.
+    /// Each pair is `(earlier_index, later_index)`, and pairs are in ascending order.
+    #[must_use]
+    pub fn overlapping_parts(&self) -> Vec<(usize, usize)> {
+        let spans = self
+            .part_bounds()
+            .into_iter()
+            .map(|(index, start, end)| (index, self.voice_parts[index].track_no, start, end))
+            .collect::<Vec<_>>();
+        overlapping_pairs(&spans)
+    }
+
+    /// Returns the index pairs of wave parts on the same track whose audio intersects on
+    /// the timeline, using [`WavePart::duration_ticks`] for their lengths.
+    ///
+    /// Pairs are ordered as in [`Project::overlapping_parts`].
+    ///
+    /// [`WavePart::duration_ticks`]: crate::WavePart::duration_ticks
+    #[must_use]
+    pub fn overlapping_wave_parts(&self) -> Vec<(usize, usize)> {
+        let spans = self
+            .wave_parts
+            .iter()
+            .enumerate()
+            .map(|(index, part)| {
+                let end = part.position.saturating_add(part.duration_ticks(self).0);
+                (index, part.track_no, part.position, end)
+            })
+            .collect::<Vec<_>>();
+        overlapping_pairs(&spans)
+    }
+
+    /// Converts `tick` to milliseconds from the start of the project using the tempo map.
+    ///
+    /// Ticks before 0 are converted at the first tempo.
+    #[must_use]
+    pub fn tick_to_ms(&self, tick: Ticks) -> Millis {
+        let Ticks(tick) = tick;
+        let segments = self.tempo_segments();
+        if tick < 0 {
+            return Millis(f64::from(tick) * self.ms_per_tick(segments[0].2));
+        }
+        let mut ms = 0.0;
+        for (start, end, bpm) in segments {
+            if tick <= start {
+                break;
+            }
+            ms += f64::from(tick.min(end) - start) * self.ms_per_tick(bpm);
+        }
+        Millis(ms)
+    }
+
+    /// Converts `ms` milliseconds from the start of the project to a fractional tick using
+    /// the tempo map.
+    ///
+    /// This is the inverse of [`Project::tick_to_ms`]. The result keeps the fraction; use
+    /// [`FractionalTicks::round`] for a whole tick.
+    #[must_use]
+    pub fn ms_to_tick(&self, ms: Millis) -> FractionalTicks {
+        let Millis(ms) = ms;
+        let segments = self.tempo_segments();
+        if ms < 0.0 {
+            return FractionalTicks(ms / self.ms_per_tick(segments[0].2));
+        }
+        let mut remaining = ms;
+        for &(start, end, bpm) in &segments {
+            let rate = self.ms_per_tick(bpm);
+            let length = f64::from(end - start) * rate;
+            if remaining < length || end == i32::MAX {
+                return FractionalTicks(f64::from(start) + remaining / rate);
+            }
+            remaining -= length;
+        }
+        FractionalTicks(f64::from(i32::MAX))
+    }
+
+    fn ms_per_tick(&self, bpm: f64) -> f64 {
+        60_000.0 / (bpm * f64::from(self.resolution))
+    }
+
+    /// Returns the time signature in effect at `bar`.
+    ///
+    /// This is the last time signature at or before `bar`. Bars before the first time
+    /// signature use the first one, and a project without time signatures falls back to
+    /// `beat_per_bar` and `beat_unit`.
+    #[must_use]
+    pub fn time_signature_at(&self, bar: i32) -> TimeSignature {
+        self.time_signatures
+            .iter()
+            .take_while(|time_signature| time_signature.bar_position <= bar)
+            .last()
+            .or_else(|| self.time_signatures.first())
+            .map_or_else(
+                || TimeSignature {
+                    bar_position: 0,
+                    beat_per_bar: self.beat_per_bar,
+                    beat_unit: self.beat_unit,
+                },
+                Clone::clone,
+            )
+    }
+
+    /// Returns the tick at which `bar` starts, following the time signature changes.
+    ///
+    /// Bars before the first time signature, including negative bars, use the initial
+    /// time signature as in [`Project::time_signature_at`]. Ticks outside the `i32`
+    /// range are clamped to it. `time_signatures` is assumed to already be sorted by
+    /// `bar_position`.
+    #[must_use]
+    pub fn bar_to_tick(&self, bar: i32) -> i32 {
+        let mut current = self.time_signature_at(0);
+        let mut current_bar = 0;
+        let mut tick = 0_i64;
+        for time_signature in &self.time_signatures {
+            if time_signature.bar_position > bar {
+                break;
+            }
+            if time_signature.bar_position > current_bar {
+                tick += (i64::from(time_signature.bar_position) - i64::from(current_bar))
+                    * i64::from(current.ticks_per_bar(self.resolution));
+                current_bar = time_signature.bar_position;
+                current = time_signature.clone();
+            }
+        }
+        tick += (i64::from(bar) - i64::from(current_bar))
+            * i64::from(current.ticks_per_bar(self.resolution));
+        saturate(tick)
+    }
+
+    /// Returns the tempo map as `(start_tick, end_tick_exclusive, bpm)` spans.
+    ///
+    /// The spans are contiguous, start at tick 0 and cover the whole project: the last
+    /// one ends at `i32::MAX`. Like [`Project::tempo_at_tick`], ticks before the first
+    /// tempo use the first tempo, a later tempo at the same position wins, and a project
+    /// without tempos yields a single span at `bpm`. `tempos` is assumed to already be
+    /// sorted by `position`.
+    #[must_use]
+    pub fn tempo_segments(&self) -> Vec<(i32, i32, f64)> {
+        if self.tempos.is_empty() {
+            return vec![(0, i32::MAX, self.bpm)];
+        }
+        let mut segments = Vec::with_capacity(self.tempos.len());
+        let mut start = 0;
+        for (index, tempo) in self.tempos.iter().enumerate() {
+            let end = self
+                .tempos
+                .get(index + 1)
+                .map_or(i32::MAX, |next| next.position);
+            if end > start {
+                segments.push((start, end, tempo.bpm));
+                start = end;
+            }
+        }
+        segments
+    }
+}
+
+/// Removes every item at position 0 except the last one, which supersedes the others.
+fn keep_last_at_start<T>(items: &mut Vec<T>, position: impl Fn(&T) -> i32) {
+    if let Some(last) = items.iter().rposition(|item| position(item) == 0) {
+        let mut index = 0;
+        items.retain(|item| {
+            index += 1;
+            index - 1 == last || position(item) != 0
+        });
+    }
+}
+
+/// Converts `value` to `i32`, clamping it to the `i32` range.
+#[allow(clippy::cast_possible_truncation)]
+const fn saturate(value: i64) -> i32 {
+    if value > i32::MAX as i64 {
+        i32::MAX
+    } else if value < i32::MIN as i64 {
+        i32::MIN
+    } else {
+        value as i32
+    }
+}
+
 #[inline]
 const fn default_bpm() -> f64 {
     120.0
@@ -62,3 +506,23 @@ const fn default_beat_per_bar() -> i32 {
 const fn default_beat_unit() -> i32 {
     4
 }
+
+/// Returns the index pairs of `(index, track_no, start, end)` spans that share a track
+/// and intersect. Empty spans never overlap anything.
+fn overlapping_pairs(spans: &[(usize, i32, i32, i32)]) -> Vec<(usize, usize)> {
+    let mut pairs = Vec::new();
+    for (offset, &(a, track_a, start_a, end_a)) in spans.iter().enumerate() {
+        for &(b, track_b, start_b, end_b) in &spans[offset + 1..] {
+            if track_a == track_b
+                && start_a < end_a
+                && start_b < end_b
+                && start_a < end_b
+                && start_b < end_a
+            {
+                pairs.push((a.min(b), a.max(b)));
+            }
+        }
+    }
+    pairs.sort_unstable();
+    pairs
+}
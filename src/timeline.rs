@@ -0,0 +1,134 @@
+use crate::project::Project;
+
+/// Represents an event on a project's timeline, tagged with its absolute tick.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TimelineEvent {
+    /// The tempo changes.
+    TempoChange {
+        /// The tick of the change.
+        tick: i32,
+        /// The new tempo in beats per minute.
+        bpm: f64,
+    },
+    /// The time signature changes at the start of a bar.
+    TimeSignatureChange {
+        /// The tick of the change.
+        tick: i32,
+        /// The bar at which the change happens.
+        bar: i32,
+        /// The number of beats per bar.
+        beat_per_bar: i32,
+        /// The beat unit.
+        beat_unit: i32,
+    },
+    /// A note starts.
+    NoteOn {
+        /// The tick at which the note starts.
+        tick: i32,
+        /// The index of the voice part containing the note.
+        part_index: usize,
+        /// The index of the note within its part.
+        note_index: usize,
+        /// The tone of the note, as a MIDI note number.
+        tone: i32,
+    },
+    /// A note ends.
+    NoteOff {
+        /// The tick at which the note ends.
+        tick: i32,
+        /// The index of the voice part containing the note.
+        part_index: usize,
+        /// The index of the note within its part.
+        note_index: usize,
+        /// The tone of the note, as a MIDI note number.
+        tone: i32,
+    },
+    /// A wave part starts playing.
+    WavePartStart {
+        /// The tick at which the part starts.
+        tick: i32,
+        /// The index of the wave part.
+        part_index: usize,
+    },
+}
+
+impl TimelineEvent {
+    /// Returns the absolute tick of the event.
+    #[inline]
+    #[must_use]
+    pub const fn tick(&self) -> i32 {
+        match self {
+            Self::TempoChange { tick, .. }
+            | Self::TimeSignatureChange { tick, .. }
+            | Self::NoteOn { tick, .. }
+            | Self::NoteOff { tick, .. }
+            | Self::WavePartStart { tick, .. } => *tick,
+        }
+    }
+
+    /// Returns the order of the event among events at the same tick.
+    ///
+    /// Meta events come first so they apply to everything at their tick, and notes end
+    /// before new ones start so back-to-back notes of the same tone stay separate.
+    const fn rank(&self) -> u8 {
+        match self {
+            Self::TimeSignatureChange { .. } => 0,
+            Self::TempoChange { .. } => 1,
+            Self::NoteOff { .. } => 2,
+            Self::WavePartStart { .. } => 3,
+            Self::NoteOn { .. } => 4,
+        }
+    }
+}
+
+impl Project {
+    /// Returns every tempo change, time signature change, note start and end, and wave
+    /// part start as one stream sorted by absolute tick.
+    ///
+    /// Note ticks are the part's `position` plus the note's, and time signature ticks are
+    /// computed with [`Project::bar_to_tick`]. At the same tick, time signature changes
+    /// come first, then tempo changes, note ends, wave part starts, and note starts;
+    /// events of the same kind keep their storage order.
+    #[must_use]
+    pub fn timeline_events(&self) -> Vec<TimelineEvent> {
+        let mut events = Vec::new();
+        for time_signature in &self.time_signatures {
+            events.push(TimelineEvent::TimeSignatureChange {
+                tick: self.bar_to_tick(time_signature.bar_position),
+                bar: time_signature.bar_position,
+                beat_per_bar: time_signature.beat_per_bar,
+                beat_unit: time_signature.beat_unit,
+            });
+        }
+        for tempo in &self.tempos {
+            events.push(TimelineEvent::TempoChange {
+                tick: tempo.position,
+                bpm: tempo.bpm,
+            });
+        }
+        for (part_index, part) in self.voice_parts.iter().enumerate() {
+            for (note_index, note) in part.notes.iter().enumerate() {
+                events.push(TimelineEvent::NoteOn {
+                    tick: part.position.saturating_add(note.position),
+                    part_index,
+                    note_index,
+                    tone: note.tone,
+                });
+                events.push(TimelineEvent::NoteOff {
+                    tick: part.position.saturating_add(note.end()),
+                    part_index,
+                    note_index,
+                    tone: note.tone,
+                });
+            }
+        }
+        for (part_index, part) in self.wave_parts.iter().enumerate() {
+            events.push(TimelineEvent::WavePartStart {
+                tick: part.position,
+                part_index,
+            });
+        }
+        events.sort_by_key(|event| (event.tick(), event.rank()));
+        events
+    }
+}
@@ -1,9 +1,20 @@
 use serde::{Deserialize, Serialize};
 
-use crate::expression::Expression;
+#[cfg(feature = "yaml")]
+use std::fs;
+#[cfg(feature = "yaml")]
+use std::path::{Path, PathBuf};
+use std::ptr;
+
+use crate::boolish;
+#[cfg(feature = "yaml")]
+use crate::error::Error;
+use crate::expression::{Expression, ExpressionDescriptor};
+use crate::part::{VoicePart, WavePart};
+use crate::project::Project;
 
 /// Represents the render settings for a track.
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
 #[serde(rename_all = "snake_case")]
 pub struct RenderSettings {
     /// The name of the renderer.
@@ -18,7 +29,7 @@ pub struct RenderSettings {
 }
 
 /// Represents a track in an `OpenUtau` project.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub struct Track {
     /// The name of the singer for this track.
@@ -37,10 +48,10 @@ pub struct Track {
     #[serde(default = "default_track_color")]
     pub track_color: String,
     /// Whether the track is muted.
-    #[serde(default)]
+    #[serde(default, deserialize_with = "boolish::deserialize")]
     pub mute: bool,
     /// Whether the track is soloed.
-    #[serde(default)]
+    #[serde(default, deserialize_with = "boolish::deserialize")]
     pub solo: bool,
     /// The volume of the track, from -12.0 to 12.0 dB.
     #[serde(default)]
@@ -75,6 +86,332 @@ impl Default for Track {
     }
 }
 
+impl Track {
+    /// The lowest volume `OpenUtau` supports, in dB.
+    pub const MIN_VOLUME: f64 = -12.0;
+    /// The highest volume `OpenUtau` supports, in dB.
+    pub const MAX_VOLUME: f64 = 12.0;
+    /// The leftmost pan position.
+    pub const MIN_PAN: f64 = -1.0;
+    /// The rightmost pan position.
+    pub const MAX_PAN: f64 = 1.0;
+
+    /// Returns the serialized key names of the track's fields, in declaration order.
+    #[inline]
+    #[must_use]
+    pub const fn field_names() -> &'static [&'static str] {
+        &[
+            "singer",
+            "phonemizer",
+            "renderer_settings",
+            "track_name",
+            "track_color",
+            "mute",
+            "solo",
+            "volume",
+            "pan",
+            "track_expressions",
+            "voice_color_names",
+        ]
+    }
+
+    /// Returns the volume clamped to [`Track::MIN_VOLUME`]..=[`Track::MAX_VOLUME`].
+    ///
+    /// A `NaN` volume is treated as 0 dB.
+    #[inline]
+    #[must_use]
+    pub const fn normalized_volume(&self) -> f64 {
+        clamp_level(self.volume, Self::MIN_VOLUME, Self::MAX_VOLUME)
+    }
+
+    /// Returns the pan clamped to [`Track::MIN_PAN`]..=[`Track::MAX_PAN`].
+    ///
+    /// A `NaN` pan is treated as centered.
+    #[inline]
+    #[must_use]
+    pub const fn normalized_pan(&self) -> f64 {
+        clamp_level(self.pan, Self::MIN_PAN, Self::MAX_PAN)
+    }
+
+    /// Clamps `volume` and `pan` into their supported ranges.
+    #[inline]
+    pub const fn clamp_levels(&mut self) {
+        self.volume = self.normalized_volume();
+        self.pan = self.normalized_pan();
+    }
+
+    /// Sets whether the track is muted.
+    #[inline]
+    pub const fn set_muted(&mut self, muted: bool) {
+        self.mute = muted;
+    }
+
+    /// Sets whether the track is soloed.
+    #[inline]
+    pub const fn set_solo(&mut self, solo: bool) {
+        self.solo = solo;
+    }
+
+    /// Resets the track's mixer settings: `volume` and `pan` to 0 and `mute` and `solo`
+    /// to `false`.
+    #[inline]
+    pub const fn reset_mix(&mut self) {
+        self.volume = 0.0;
+        self.pan = 0.0;
+        self.mute = false;
+        self.solo = false;
+    }
+
+    /// Returns the `clr` expression value that selects the voice color `name`, which is
+    /// its index in `voice_color_names`.
+    #[must_use]
+    pub fn color_index(&self, name: &str) -> Option<i32> {
+        self.voice_color_names
+            .iter()
+            .position(|color| color == name)
+            .and_then(|index| i32::try_from(index).ok())
+    }
+
+    /// Returns the voice color (`clr`) expression descriptor for the track's singer, with
+    /// its `voice_color_names` as the options.
+    #[must_use]
+    pub fn voice_color_descriptor(&self) -> ExpressionDescriptor {
+        ExpressionDescriptor::voice_color(self.voice_color_names.clone())
+    }
+
+    /// Returns whether the track produces sound, given whether any track in the project
+    /// is soloed.
+    ///
+    /// A muted track is never audible. When any track is soloed, only soloed tracks are.
+    #[inline]
+    #[must_use]
+    pub const fn is_audible(&self, any_solo: bool) -> bool {
+        !self.mute && (self.solo || !any_solo)
+    }
+
+    /// Returns the voice parts of `project` that belong to this track.
+    ///
+    /// The track must be an element of `project.tracks`; otherwise the iterator is empty.
+    pub fn parts<'a>(&self, project: &'a Project) -> impl Iterator<Item = &'a VoicePart> + use<'a> {
+        let index = project
+            .tracks
+            .iter()
+            .position(|track| ptr::eq(track, self))
+            .and_then(|index| i32::try_from(index).ok());
+        project
+            .voice_parts
+            .iter()
+            .filter(move |part| Some(part.track_no) == index)
+    }
+}
+
+#[cfg(feature = "yaml")]
+impl Track {
+    /// Finds the voicebank directory of the track's singer under `singers_root`.
+    ///
+    /// Each direct subdirectory of `singers_root` is checked, in path order, for a
+    /// `character.yaml` whose `name` equals `singer`. Subdirectories without a readable
+    /// `character.yaml` are skipped. Returns `None` if the singer is empty or no
+    /// voicebank matches.
+    #[must_use]
+    pub fn singer_dir(&self, singers_root: &Path) -> Option<PathBuf> {
+        if self.singer.is_empty() {
+            return None;
+        }
+        let mut dirs = fs::read_dir(singers_root)
+            .ok()?
+            .filter_map(|entry| Some(entry.ok()?.path()))
+            .filter(|path| path.is_dir())
+            .collect::<Vec<_>>();
+        dirs.sort();
+        dirs.into_iter()
+            .find(|dir| character_name(dir).is_some_and(|name| name == self.singer))
+    }
+}
+
+#[cfg(feature = "yaml")]
+impl Project {
+    /// Resolves the voicebank directory of every track's singer under `singers_root`,
+    /// indexed like `tracks`.
+    ///
+    /// Tracks without a singer resolve to `None`. Returns [`Error::SingerNotFound`] for
+    /// the first singer that is not installed.
+    pub fn singer_dirs(&self, singers_root: &Path) -> Result<Vec<Option<PathBuf>>, Error> {
+        self.tracks
+            .iter()
+            .map(|track| {
+                if track.singer.is_empty() {
+                    return Ok(None);
+                }
+                track
+                    .singer_dir(singers_root)
+                    .map(Some)
+                    .ok_or_else(|| Error::SingerNotFound(track.singer.clone()))
+            })
+            .collect()
+    }
+}
+
+/// The part of a voicebank's `character.yaml` needed to identify it.
+#[cfg(feature = "yaml")]
+#[derive(Deserialize)]
+struct Character {
+    #[serde(default)]
+    name: Option<String>,
+}
+
+/// Reads the singer name from the `character.yaml` in `dir`.
+#[cfg(feature = "yaml")]
+fn character_name(dir: &Path) -> Option<String> {
+    let contents = fs::read_to_string(dir.join("character.yaml")).ok()?;
+    serde_yaml::from_str::<Character>(crate::project::strip_bom(&contents))
+        .ok()?
+        .name
+}
+
+/// Represents a track removed by [`Project::remove_track`], together with its parts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemovedTrack {
+    /// The removed track.
+    pub track: Track,
+    /// The voice parts that belonged to the track, in their original order.
+    pub voice_parts: Vec<VoicePart>,
+    /// The wave parts that belonged to the track, in their original order.
+    pub wave_parts: Vec<WavePart>,
+}
+
+impl Project {
+    /// Removes the track at `index`, keeping every part's `track_no` consistent.
+    ///
+    /// Parts on the removed track are taken out of the project and returned, and parts on
+    /// later tracks have their `track_no` decremented. Returns `None` and leaves the
+    /// project unchanged if `index` is out of bounds.
+    pub fn remove_track(&mut self, index: usize) -> Option<RemovedTrack> {
+        if index >= self.tracks.len() {
+            return None;
+        }
+        let track_no = i32::try_from(index).ok()?;
+        let track = self.tracks.remove(index);
+        let (voice_parts, kept) = self
+            .voice_parts
+            .drain(..)
+            .partition(|part| part.track_no == track_no);
+        self.voice_parts = kept;
+        let (wave_parts, kept) = self
+            .wave_parts
+            .drain(..)
+            .partition(|part| part.track_no == track_no);
+        self.wave_parts = kept;
+        for part in &mut self.voice_parts {
+            if part.track_no > track_no {
+                part.track_no -= 1;
+            }
+        }
+        for part in &mut self.wave_parts {
+            if part.track_no > track_no {
+                part.track_no -= 1;
+            }
+        }
+        Some(RemovedTrack {
+            track,
+            voice_parts,
+            wave_parts,
+        })
+    }
+
+    /// Appends a copy of the track at `index`, along with copies of all its parts.
+    ///
+    /// The copied parts are appended after the existing ones and point at the new track.
+    /// Returns the new track's index, or `None` if `index` is out of bounds.
+    pub fn duplicate_track(&mut self, index: usize) -> Option<usize> {
+        let track = self.tracks.get(index)?.clone();
+        let track_no = i32::try_from(index).ok()?;
+        let new_index = self.tracks.len();
+        let new_track_no = i32::try_from(new_index).ok()?;
+        self.tracks.push(track);
+        let voice_parts = self
+            .voice_parts
+            .iter()
+            .filter(|part| part.track_no == track_no)
+            .map(|part| VoicePart {
+                track_no: new_track_no,
+                ..part.clone()
+            })
+            .collect::<Vec<_>>();
+        self.voice_parts.extend(voice_parts);
+        let wave_parts = self
+            .wave_parts
+            .iter()
+            .filter(|part| part.track_no == track_no)
+            .map(|part| WavePart {
+                track_no: new_track_no,
+                ..part.clone()
+            })
+            .collect::<Vec<_>>();
+        self.wave_parts.extend(wave_parts);
+        Some(new_index)
+    }
+
+    /// Returns the indexes of the tracks that produce sound given their `mute` and
+    /// `solo` flags.
+    ///
+    /// If any track is soloed, only the soloed tracks that are not muted are audible;
+    /// otherwise every track that is not muted is. See [`Track::is_audible`].
+    #[must_use]
+    pub fn audible_tracks(&self) -> Vec<usize> {
+        let any_solo = self.tracks.iter().any(|track| track.solo);
+        self.tracks
+            .iter()
+            .enumerate()
+            .filter(|(_, track)| track.is_audible(any_solo))
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Unsolos every track, so that every track that is not muted is audible again.
+    ///
+    /// Returns the number of tracks that were soloed.
+    pub fn clear_all_solo(&mut self) -> usize {
+        let mut cleared = 0;
+        for track in self.tracks.iter_mut().filter(|track| track.solo) {
+            track.set_solo(false);
+            cleared += 1;
+        }
+        cleared
+    }
+
+    /// Returns `(track_index, pan)` for every audible track, as in
+    /// [`Project::audible_tracks`].
+    ///
+    /// Pans are clamped with [`Track::normalized_pan`].
+    #[must_use]
+    pub fn pan_layout(&self) -> Vec<(usize, f64)> {
+        self.audible_tracks()
+            .into_iter()
+            .map(|index| (index, self.tracks[index].normalized_pan()))
+            .collect()
+    }
+
+    /// Returns whether no track can be heard: no track is audible, as in
+    /// [`Project::audible_tracks`], or every audible track is turned down to
+    /// [`Track::MIN_VOLUME`].
+    #[must_use]
+    pub fn is_effectively_silent(&self) -> bool {
+        self.audible_tracks()
+            .into_iter()
+            .all(|index| self.tracks[index].normalized_volume() <= Track::MIN_VOLUME)
+    }
+}
+
+#[inline]
+const fn clamp_level(value: f64, min: f64, max: f64) -> f64 {
+    if value.is_nan() {
+        0.0
+    } else {
+        value.clamp(min, max)
+    }
+}
+
 #[inline]
 fn default_track_name() -> String {
     String::from("New Track")
@@ -0,0 +1,133 @@
+use crate::error::Error;
+use crate::note::Note;
+use crate::part::VoicePart;
+use crate::project::Project;
+use crate::time::Tempo;
+use crate::track::Track;
+use crate::version::CURRENT_VERSION;
+
+impl Project {
+    /// Imports a legacy UTAU `.ust` file as a single-track, single-part `Project`.
+    ///
+    /// Each numbered `[#0000]` section becomes a note, using its `Length`, `NoteNum`,
+    /// and `Lyric` keys. Notes are laid out back to back, and rest notes (lyric `R`)
+    /// advance the position without producing a note. The project name and tempo come
+    /// from `[#SETTING]`, and per-note `Tempo` keys become tempo changes. Other sections
+    /// and keys are ignored.
+    pub fn from_ust_str(input: &str) -> Result<Self, Error> {
+        let mut project = Self {
+            tracks: vec![Track::default()],
+            ustx_version: Some(CURRENT_VERSION),
+            ..Self::default()
+        };
+        let mut part = VoicePart::default();
+        let mut section = UstSection::Other;
+        let mut position = 0;
+
+        for line in input.lines().map(str::trim) {
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix("[#").and_then(|s| s.strip_suffix(']')) {
+                position = section.finish(&mut part, position)?;
+                section = UstSection::from_name(name);
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            match &mut section {
+                UstSection::Setting => match key {
+                    "Tempo" => {
+                        let bpm = parse_ust_value(key, value)?;
+                        project.bpm = bpm;
+                        project.tempos = vec![Tempo { position: 0, bpm }];
+                    }
+                    "ProjectName" => project.name = String::from(value),
+                    _ => {}
+                },
+                UstSection::Note { fields, .. } => match key {
+                    "Length" => fields.length = Some(parse_ust_value(key, value)?),
+                    "NoteNum" => fields.tone = Some(parse_ust_value(key, value)?),
+                    "Lyric" => fields.lyric = Some(String::from(value)),
+                    "Tempo" => {
+                        let bpm = parse_ust_value(key, value)?;
+                        if position == 0 {
+                            project.bpm = bpm;
+                        }
+                        project.insert_tempo(Tempo { position, bpm });
+                    }
+                    _ => {}
+                },
+                UstSection::Other => {}
+            }
+        }
+        section.finish(&mut part, position)?;
+
+        project.voice_parts = vec![part];
+        Ok(project)
+    }
+}
+
+enum UstSection {
+    Setting,
+    Note { name: String, fields: UstNote },
+    Other,
+}
+
+#[derive(Default)]
+struct UstNote {
+    length: Option<i32>,
+    tone: Option<i32>,
+    lyric: Option<String>,
+}
+
+impl UstSection {
+    fn from_name(name: &str) -> Self {
+        if name == "SETTING" {
+            Self::Setting
+        } else if !name.is_empty() && name.bytes().all(|byte| byte.is_ascii_digit()) {
+            Self::Note {
+                name: String::from(name),
+                fields: UstNote::default(),
+            }
+        } else {
+            Self::Other
+        }
+    }
+
+    /// Appends the note collected by this section, returning the position after it.
+    fn finish(&mut self, part: &mut VoicePart, position: i32) -> Result<i32, Error> {
+        let Self::Note { name, fields } = std::mem::replace(self, Self::Other) else {
+            return Ok(position);
+        };
+        let length = fields
+            .length
+            .ok_or_else(|| Error::Ust(format!("[#{name}] is missing Length")))?;
+        if length <= 0 {
+            return Err(Error::Ust(format!("[#{name}] has non-positive Length")));
+        }
+        let lyric = fields.lyric.unwrap_or_default();
+        if !lyric.eq_ignore_ascii_case("r") {
+            let tone = fields
+                .tone
+                .ok_or_else(|| Error::Ust(format!("[#{name}] is missing NoteNum")))?;
+            part.notes.push(
+                Note::builder()
+                    .position(position)
+                    .duration(length)
+                    .tone(tone)
+                    .lyric(lyric)
+                    .build(),
+            );
+        }
+        Ok(position + length)
+    }
+}
+
+fn parse_ust_value<T: std::str::FromStr>(key: &str, value: &str) -> Result<T, Error> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| Error::Ust(format!("invalid {key} value: {value}")))
+}
@@ -0,0 +1,180 @@
+use std::fmt::{self, Display, Formatter};
+
+use crate::project::Project;
+use crate::track::Track;
+
+/// Represents a problem found by [`Project::validate`].
+///
+/// Issues describe data that parses but is outside what `OpenUtau` expects. None of them
+/// prevent the project from being loaded or saved.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationIssue {
+    /// The project resolution is not positive, which makes tick-to-time conversion
+    /// impossible.
+    InvalidResolution(i32),
+    /// More than one time signature starts at this bar.
+    DuplicateTimeSignature(i32),
+    /// More than one tempo starts at this tick.
+    DuplicateTempoPosition(i32),
+    /// A track's volume is outside [`Track::MIN_VOLUME`]..=[`Track::MAX_VOLUME`].
+    VolumeOutOfRange {
+        /// The index of the track.
+        track_index: usize,
+        /// The stored volume.
+        volume: f64,
+    },
+    /// A track's pan is outside [`Track::MIN_PAN`]..=[`Track::MAX_PAN`].
+    PanOutOfRange {
+        /// The index of the track.
+        track_index: usize,
+        /// The stored pan.
+        pan: f64,
+    },
+    /// A voice part refers to a track that does not exist.
+    OrphanPart {
+        /// The index of the voice part.
+        part_index: usize,
+        /// The track number the part refers to.
+        track_no: i32,
+    },
+    /// A wave part refers to a track that does not exist.
+    OrphanWavePart {
+        /// The index of the wave part.
+        part_index: usize,
+        /// The track number the part refers to.
+        track_no: i32,
+    },
+}
+
+impl Display for ValidationIssue {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidResolution(resolution) => {
+                write!(f, "resolution {resolution} is not positive")
+            }
+            Self::DuplicateTimeSignature(bar) => {
+                write!(f, "multiple time signatures at bar {bar}")
+            }
+            Self::DuplicateTempoPosition(tick) => write!(f, "multiple tempos at tick {tick}"),
+            Self::VolumeOutOfRange {
+                track_index,
+                volume,
+            } => write!(f, "track {track_index}: volume {volume} dB out of range"),
+            Self::PanOutOfRange { track_index, pan } => {
+                write!(f, "track {track_index}: pan {pan} out of range")
+            }
+            Self::OrphanPart {
+                part_index,
+                track_no,
+            } => write!(
+                f,
+                "voice part {part_index}: track {track_no} does not exist"
+            ),
+            Self::OrphanWavePart {
+                part_index,
+                track_no,
+            } => write!(f, "wave part {part_index}: track {track_no} does not exist"),
+        }
+    }
+}
+
+/// The most tracks [`Project::ensure_tracks_for_parts`] fills a project up to, so that a
+/// corrupted track number cannot make it allocate millions of tracks.
+const MAX_REPAIRED_TRACKS: usize = 1024;
+
+impl Project {
+    /// Checks the project for values outside the ranges `OpenUtau` supports.
+    ///
+    /// Returns every issue found, in project field order. An empty `Vec` means the
+    /// project is valid.
+    #[must_use]
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+        if self.resolution <= 0 {
+            issues.push(ValidationIssue::InvalidResolution(self.resolution));
+        }
+        issues.extend(
+            duplicates(
+                self.time_signatures
+                    .iter()
+                    .map(|time_signature| time_signature.bar_position),
+            )
+            .map(ValidationIssue::DuplicateTimeSignature),
+        );
+        issues.extend(
+            duplicates(self.tempos.iter().map(|tempo| tempo.position))
+                .map(ValidationIssue::DuplicateTempoPosition),
+        );
+        for (track_index, track) in self.tracks.iter().enumerate() {
+            if !(Track::MIN_VOLUME..=Track::MAX_VOLUME).contains(&track.volume) {
+                issues.push(ValidationIssue::VolumeOutOfRange {
+                    track_index,
+                    volume: track.volume,
+                });
+            }
+            if !(Track::MIN_PAN..=Track::MAX_PAN).contains(&track.pan) {
+                issues.push(ValidationIssue::PanOutOfRange {
+                    track_index,
+                    pan: track.pan,
+                });
+            }
+        }
+        let has_track =
+            |track_no: i32| usize::try_from(track_no).is_ok_and(|index| index < self.tracks.len());
+        for (part_index, part) in self.voice_parts.iter().enumerate() {
+            if !has_track(part.track_no) {
+                issues.push(ValidationIssue::OrphanPart {
+                    part_index,
+                    track_no: part.track_no,
+                });
+            }
+        }
+        for (part_index, part) in self.wave_parts.iter().enumerate() {
+            if !has_track(part.track_no) {
+                issues.push(ValidationIssue::OrphanWavePart {
+                    part_index,
+                    track_no: part.track_no,
+                });
+            }
+        }
+        issues
+    }
+
+    /// Appends default tracks until every track number referenced by a voice or wave
+    /// part exists.
+    ///
+    /// Returns the number of tracks added. Negative track numbers, and track numbers that
+    /// would take the project past 1024 tracks, cannot be repaired this way and are
+    /// ignored; [`Project::validate`] keeps reporting their parts.
+    pub fn ensure_tracks_for_parts(&mut self) -> usize {
+        let required = self
+            .voice_parts
+            .iter()
+            .map(|part| part.track_no)
+            .chain(self.wave_parts.iter().map(|part| part.track_no))
+            .filter_map(|track_no| usize::try_from(track_no).ok())
+            .map(|index| index + 1)
+            .filter(|&count| count <= MAX_REPAIRED_TRACKS)
+            .max()
+            .unwrap_or(0);
+        let added = required.saturating_sub(self.tracks.len());
+        self.tracks
+            .resize_with(self.tracks.len() + added, Track::default);
+        added
+    }
+}
+
+/// Returns every value that occurs more than once in `values`, once each, in ascending
+/// order.
+fn duplicates(values: impl Iterator<Item = i32>) -> impl Iterator<Item = i32> {
+    let mut values = values.collect::<Vec<_>>();
+    values.sort_unstable();
+    let mut duplicates = values
+        .windows(2)
+        .filter(|pair| pair[0] == pair[1])
+        .map(|pair| pair[0])
+        .collect::<Vec<_>>();
+    duplicates.dedup();
+    duplicates.into_iter()
+}
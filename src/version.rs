@@ -1,15 +1,23 @@
 use semver::Version as SemverVersion;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::cmp::Ordering;
 use std::fmt::{self, Display, Formatter};
+use std::hash::{Hash, Hasher};
 use std::str::FromStr;
 
 pub const CURRENT_VERSION: Version = Version::new(0, 7, 0);
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy)]
 pub struct Version {
     pub major: u64,
     pub minor: u64,
     pub patch: u64,
+    /// The number of dot-separated components the version was parsed from, such as 3
+    /// for `0.3.0`, or 0 if it was not parsed.
+    ///
+    /// Serialization writes at least this many components, so unmodified files
+    /// round-trip byte for byte. It is ignored by comparisons and hashing.
+    components: u8,
 }
 
 impl Version {
@@ -20,6 +28,7 @@ impl Version {
             major,
             minor,
             patch,
+            components: 0,
         }
     }
 
@@ -45,19 +54,19 @@ impl Version {
 
     fn parse_with_fallback(input: &str) -> Result<Self, semver::Error> {
         match SemverVersion::parse(input) {
-            Ok(version) => Ok(Self::from_semver(&version)),
+            Ok(version) => Ok(Self::from_semver(&version).with_components(3)),
             Err(original_error) => {
                 if input.contains(['-', '+']) {
                     return Err(original_error);
                 }
                 let mut segments = input.split('.').collect::<Vec<_>>();
-                if segments.len() >= 3 {
+                let Ok(components @ ..3) = u8::try_from(segments.len()) else {
                     return Err(original_error);
-                }
+                };
                 segments.resize(3, "0");
                 let normalized = segments.join(".");
                 SemverVersion::parse(&normalized)
-                    .map(|version| Self::from_semver(&version))
+                    .map(|version| Self::from_semver(&version).with_components(components))
                     .map_err(|_| original_error)
             }
         }
@@ -68,6 +77,46 @@ impl Version {
     const fn from_semver(version: &SemverVersion) -> Self {
         Self::new(version.major, version.minor, version.patch)
     }
+
+    #[inline]
+    const fn with_components(self, components: u8) -> Self {
+        Self { components, ..self }
+    }
+
+    #[inline]
+    const fn key(self) -> (u64, u64, u64) {
+        (self.major, self.minor, self.patch)
+    }
+}
+
+impl PartialEq for Version {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for Version {}
+
+impl Hash for Version {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key().hash(state);
+    }
+}
+
+impl PartialOrd for Version {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Version {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
 }
 
 impl FromStr for Version {
@@ -99,7 +148,14 @@ impl Serialize for Version {
     where
         S: Serializer,
     {
-        serializer.serialize_str(&self.to_string())
+        match self.components {
+            3 => serializer.collect_str(&format_args!(
+                "{}.{}.{}",
+                self.major, self.minor, self.patch
+            )),
+            1 if self.minor == 0 && self.patch == 0 => serializer.collect_str(&self.major),
+            _ => serializer.collect_str(self),
+        }
     }
 }
 
@@ -1,4 +1,11 @@
-use ustx::{CURRENT_VERSION, Project};
+#![cfg(feature = "yaml")]
+
+use std::path::Path;
+use ustx::{
+    CURRENT_VERSION, Expression, ExpressionDescriptor, ExpressionType, Note, Phoneme, PhonemeError,
+    PhonemeOverride, PitchPointShape, Project, ProjectChange, Scale, Tempo, TimeSignature,
+    ValidationIssue, Vibrato,
+};
 
 fn sample_yaml() -> &'static str {
     r#"name: Demo
@@ -33,6 +40,24 @@ fn parses_and_maintains_yaml() {
     assert_eq!(project_roundtrip.comment, "sample");
 }
 
+#[test]
+fn parses_fuzz_seed_fixtures_without_panicking() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data");
+    let mut parsed = Vec::new();
+    for entry in std::fs::read_dir(&dir).expect("read fixtures") {
+        let path = entry.expect("fixture entry").path();
+        let bytes = std::fs::read(&path).expect("read fixture");
+        if let Ok(project) = Project::from_yaml_bytes(&bytes) {
+            project
+                .to_yaml_string()
+                .expect("a parsed project must serialize");
+            parsed.push(project.name);
+        }
+    }
+    parsed.sort();
+    assert_eq!(parsed, ["Bom", "Demo", "Full", "Legacy"]);
+}
+
 #[test]
 fn compat_upgrade_fills_defaults() {
     let project = Project::from_yaml_str_with_compat(sample_yaml()).expect("upgrade");
@@ -51,3 +76,2983 @@ fn raw_parse_preserves_version_number() {
     assert_eq!(version.minor, 3);
     assert_eq!(version.patch, 0);
 }
+
+#[test]
+fn vibrato_offset_follows_envelope() {
+    let vibrato = Vibrato {
+        length: 50.0,
+        period: 100.0,
+        depth: 20.0,
+        r#in: 0.0,
+        out: 0.0,
+        ..Vibrato::default()
+    };
+    assert_eq!(vibrato.pitch_offset_at(1000.0, 100.0), 0.0);
+    assert!((vibrato.pitch_offset_at(1000.0, 525.0) - 20.0).abs() < 1e-3);
+    assert!((vibrato.pitch_offset_at(1000.0, 575.0) + 20.0).abs() < 1e-3);
+
+    let faded = Vibrato {
+        length: 50.0,
+        period: 100.0,
+        depth: 20.0,
+        r#in: 50.0,
+        out: 0.0,
+        ..Vibrato::default()
+    };
+    assert!(faded.pitch_offset_at(1000.0, 525.0).abs() < 20.0 * 0.25);
+    assert_eq!(Vibrato::default().pitch_offset_at(1000.0, 900.0), 0.0);
+}
+
+fn sample_note() -> Note {
+    Project::from_yaml_str("voice_parts:\n- notes:\n  - tone: 60\n")
+        .expect("parse note")
+        .voice_parts
+        .remove(0)
+        .notes
+        .remove(0)
+}
+
+#[test]
+fn phoneme_validation_and_dedup() {
+    let mut note = sample_note();
+    assert_eq!(note.validate_phonemes(), Ok(()));
+
+    note.phoneme_overrides = vec![
+        PhonemeOverride {
+            index: 0,
+            phoneme: Some("a".into()),
+            ..PhonemeOverride::default()
+        },
+        PhonemeOverride {
+            index: 1,
+            ..PhonemeOverride::default()
+        },
+        PhonemeOverride {
+            index: 0,
+            phoneme: Some("b".into()),
+            ..PhonemeOverride::default()
+        },
+    ];
+    assert_eq!(
+        note.validate_phonemes(),
+        Err(PhonemeError::DuplicateOverrideIndex(0))
+    );
+    note.dedup_phoneme_overrides();
+    assert_eq!(note.phoneme_overrides.len(), 2);
+    assert_eq!(note.phoneme_overrides[0].index, 1);
+    assert_eq!(note.phoneme_overrides[1].phoneme.as_deref(), Some("b"));
+    assert_eq!(note.validate_phonemes(), Ok(()));
+
+    note.phoneme_indexes = vec![0, 2, 2];
+    assert_eq!(
+        note.validate_phonemes(),
+        Err(PhonemeError::DuplicatePhonemeIndex(2))
+    );
+    note.phoneme_indexes = vec![-1];
+    assert_eq!(
+        note.validate_phonemes(),
+        Err(PhonemeError::NegativePhonemeIndex(-1))
+    );
+}
+
+#[test]
+fn parses_multiple_documents() {
+    let input = format!("{}---\nname: Second\n", sample_yaml());
+    let projects = Project::from_yaml_multi(&input).expect("parse documents");
+    assert_eq!(projects.len(), 2);
+    assert_eq!(projects[0].name, "Demo");
+    assert_eq!(projects[1].name, "Second");
+
+    assert!(Project::from_yaml_multi("").expect("empty").is_empty());
+
+    let trailing = Project::from_yaml_multi("name: Only\n---\n").expect("trailing separator");
+    assert_eq!(trailing.len(), 1);
+    assert_eq!(trailing[0].name, "Only");
+}
+
+#[test]
+fn default_matches_empty_document() {
+    let parsed = Project::from_yaml_str("{}").expect("parse empty map");
+    assert_eq!(
+        Project::default()
+            .to_yaml_string()
+            .expect("serialize default"),
+        parsed.to_yaml_string().expect("serialize parsed")
+    );
+}
+
+#[test]
+fn reports_missing_wave_files() {
+    let project = Project::from_yaml_str(
+        "wave_parts:\n- relative_path: Cargo.toml\n- relative_path: missing/audio.wav\n",
+    )
+    .expect("parse wave parts");
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    assert!(project.wave_parts[0].audio_exists(dir));
+    assert_eq!(
+        project.wave_parts[1].resolve_path(dir),
+        dir.join("missing/audio.wav")
+    );
+    let missing = project.missing_wave_files(dir);
+    assert_eq!(missing.len(), 1);
+    assert_eq!(missing[0].relative_path, "missing/audio.wav");
+}
+
+fn descriptor(r#type: ExpressionType, min: f32, max: f32) -> ExpressionDescriptor {
+    ExpressionDescriptor {
+        name: String::from("test"),
+        abbr: String::from("tst"),
+        r#type,
+        min,
+        max,
+        default_value: 0.0,
+        is_flag: false,
+        flag: None,
+        options: Vec::new(),
+    }
+}
+
+#[test]
+fn expression_constructors_and_clamping() {
+    let mut expression = Expression::numerical("vel", 250.0);
+    assert_eq!(expression.abbr, "vel");
+    assert_eq!(expression.index, None);
+
+    expression.clamp(&descriptor(ExpressionType::Numerical, 0.0, 200.0));
+    assert_eq!(expression.value, 200.0);
+    expression.value = -5.0;
+    expression.clamp(&descriptor(ExpressionType::Numerical, 0.0, 200.0));
+    assert_eq!(expression.value, 0.0);
+
+    let mut options = descriptor(ExpressionType::Options, 0.0, 1.0);
+    options.options = vec![String::from("off"), String::from("on")];
+    assert!(Expression::numerical("opt", 1.0).is_valid_option(&options));
+    assert!(!Expression::numerical("opt", 2.0).is_valid_option(&options));
+    assert!(!Expression::numerical("opt", 0.5).is_valid_option(&options));
+    assert!(!Expression::numerical("opt", -1.0).is_valid_option(&options));
+}
+
+#[test]
+fn maps_lyrics_skipping_connectors() {
+    let mut project = Project::from_yaml_str(
+        "voice_parts:\n- notes:\n  - lyric: ka\n  - lyric: +\n  - lyric: ...\n  - lyric: na\n",
+    )
+    .expect("parse notes");
+    project.convert_to(CURRENT_VERSION).expect("convert");
+    let part = &mut project.voice_parts[0];
+    assert!(part.notes[1].is_connector());
+    assert!(part.notes[2].is_connector());
+    part.map_lyrics(str::to_uppercase);
+    let lyrics = part
+        .notes
+        .iter()
+        .map(|note| note.lyric.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(lyrics, ["KA", "+", "+", "NA"]);
+}
+
+#[test]
+fn content_hash_detects_conversion_changes() {
+    let mut project = Project::from_yaml_str(sample_yaml()).expect("parse");
+    let reparsed = Project::from_yaml_str(sample_yaml()).expect("reparse");
+    let before = project.content_hash();
+    assert_eq!(before, reparsed.content_hash());
+
+    project.convert_to(CURRENT_VERSION).expect("convert");
+    let after = project.content_hash();
+    assert_ne!(before, after);
+
+    project.convert_to(CURRENT_VERSION).expect("convert again");
+    assert_eq!(after, project.content_hash());
+}
+
+struct FailingReader;
+
+impl std::io::Read for FailingReader {
+    fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+        Err(std::io::Error::other("boom"))
+    }
+}
+
+#[test]
+fn reads_from_reader_and_writes_to_writer() {
+    let project = Project::from_reader(sample_yaml().as_bytes()).expect("read project");
+    assert_eq!(project.name, "Demo");
+
+    let mut buffer = Vec::new();
+    project.to_writer(&mut buffer).expect("write project");
+    let written = String::from_utf8(buffer).expect("utf-8 output");
+    assert_eq!(written, project.to_yaml_string().expect("serialize"));
+
+    assert!(matches!(
+        Project::from_reader(FailingReader),
+        Err(ustx::Error::Io(_))
+    ));
+}
+
+#[test]
+fn snaps_tones_to_key() {
+    let mut project = Project::from_yaml_str(
+        "key: 2\nvoice_parts:\n- notes:\n  - tone: 62\n  - tone: 63\n  - tone: 65\n  - tone: 49\n",
+    )
+    .expect("parse notes");
+    project.snap_to_key(Scale::Major);
+    let tones = project.voice_parts[0]
+        .notes
+        .iter()
+        .map(|note| note.tone)
+        .collect::<Vec<_>>();
+    assert_eq!(tones, [62, 64, 66, 49]);
+
+    assert_eq!(Scale::Minor.snap(0, 64), 65);
+    assert_eq!(Scale::Chromatic.snap(0, 61), 61);
+    assert!(Scale::Major.contains(-1, 59));
+    assert_eq!(Scale::Major.snap(i32::MIN, 61), 61);
+    assert!(Scale::Major.contains(i32::MAX, i32::MIN));
+    assert_eq!(Scale::Major.snap(1, i32::MAX), i32::MAX - 1);
+    assert_eq!(Scale::Major.snap(1, i32::MIN), i32::MIN + 1);
+}
+
+#[test]
+fn roundtrip_has_empty_diff() {
+    let project = Project::from_yaml_str(sample_yaml()).expect("parse");
+    let serialized = project.to_yaml_string().expect("serialize");
+    let roundtrip = Project::from_yaml_str(&serialized).expect("reparse");
+    assert!(project.diff(&roundtrip).is_empty());
+}
+
+#[test]
+fn upgrades_in_memory() {
+    let project = Project::from_yaml_str(sample_yaml()).expect("parse");
+    let upgraded = project.upgraded().expect("upgrade");
+    assert_eq!(upgraded.ustx_version, Some(CURRENT_VERSION));
+    assert_eq!(
+        upgraded,
+        Project::from_yaml_str_with_compat(sample_yaml()).expect("compat")
+    );
+    let future = Project::default().with_version(ustx::Version::new(99, 0, 0));
+    assert!(future.upgraded().is_err());
+
+    let value = serde_yaml::from_str::<serde_yaml::Value>("resolution: 0\n").expect("value");
+    let unresolved = Project::try_from(value).expect("convert value");
+    assert!(matches!(
+        unresolved.upgraded(),
+        Err(ustx::Error::InvalidResolution(0))
+    ));
+}
+
+#[test]
+fn diff_summarizes_conversion() {
+    let original = Project::from_yaml_str(sample_yaml()).expect("parse");
+    let mut converted = original.clone();
+    converted.convert_to(CURRENT_VERSION).expect("convert");
+    converted.voice_parts.push(ustx::VoicePart {
+        notes: vec![sample_note()],
+        ..Project::from_yaml_str("voice_parts:\n- {}\n")
+            .expect("part")
+            .voice_parts[0]
+            .clone()
+    });
+
+    let diff = original.diff(&converted);
+    assert_eq!(
+        diff.changes,
+        [
+            ProjectChange::FieldChanged("ustx_version"),
+            ProjectChange::FieldChanged("exp_selectors"),
+            ProjectChange::FieldChanged("time_signatures"),
+            ProjectChange::FieldChanged("tempos"),
+            ProjectChange::VoicePartAdded(0),
+        ]
+    );
+    assert_eq!(
+        diff.to_string(),
+        "changed ustx_version, changed exp_selectors, changed time_signatures, changed tempos, added voice part 0"
+    );
+}
+
+#[test]
+fn parses_enum_names() {
+    for shape in [
+        PitchPointShape::Io,
+        PitchPointShape::L,
+        PitchPointShape::I,
+        PitchPointShape::O,
+    ] {
+        let parsed: PitchPointShape = shape.as_str().parse().expect("parse shape");
+        assert_eq!(parsed.as_str(), shape.as_str());
+        let yaml = serde_yaml::to_string(&shape).expect("serialize shape");
+        assert_eq!(yaml.trim(), shape.as_str());
+    }
+    for kind in [
+        ExpressionType::Numerical,
+        ExpressionType::Options,
+        ExpressionType::Curve,
+    ] {
+        let parsed: ExpressionType = kind.as_str().parse().expect("parse type");
+        assert_eq!(parsed.as_str(), kind.as_str());
+        let yaml = serde_yaml::to_string(&kind).expect("serialize type");
+        assert_eq!(yaml.trim(), kind.as_str());
+    }
+    assert!(matches!(
+        "sine".parse::<PitchPointShape>(),
+        Err(ustx::Error::InvalidValue { .. })
+    ));
+    assert!("Curve".parse::<ExpressionType>().is_err());
+}
+
+#[test]
+fn renames_renderers() {
+    let mut project = Project::from_yaml_str(
+        "tracks:\n- renderer_settings:\n    renderer: CLASSIC\n- renderer_settings:\n    renderer: WORLDLINE-R\n- renderer_settings:\n    renderer: CLASSIC\n- {}\n",
+    )
+    .expect("parse tracks");
+    assert_eq!(
+        project.renderers().into_iter().collect::<Vec<_>>(),
+        ["CLASSIC", "WORLDLINE-R"]
+    );
+    assert_eq!(project.set_renderer("CLASSIC", "ENUNU"), 2);
+    assert_eq!(project.set_renderer("CLASSIC", "ENUNU"), 0);
+    assert_eq!(
+        project.renderers().into_iter().collect::<Vec<_>>(),
+        ["ENUNU", "WORLDLINE-R"]
+    );
+}
+
+#[test]
+fn slices_part_by_tick_range() {
+    let project = Project::from_yaml_str(
+        r"
+voice_parts:
+- position: 960
+  notes:
+  - {position: 0, duration: 480, lyric: a}
+  - {position: 480, duration: 480, lyric: b}
+  - {position: 960, duration: 480, lyric: c}
+  - {position: 1200, duration: 480, lyric: d}
+  curves:
+  - abbr: dyn
+    xs: [0, 480, 900, 1440]
+    ys: [1, 2, 3, 4]
+",
+    )
+    .expect("parse part");
+    let slice = project.voice_parts[0].slice(480, 1440);
+    assert_eq!(slice.position, 1440);
+    let notes = slice
+        .notes
+        .iter()
+        .map(|note| (note.position, note.lyric.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(notes, [(0, "b"), (480, "c")]);
+    assert_eq!(slice.curves[0].xs, [0, 420, 960]);
+    assert_eq!(slice.curves[0].ys, [2, 3, 4]);
+
+    let slice = project.voice_parts[0].slice(240, 1200);
+    assert_eq!(slice.curves[0].xs, [0, 240, 660, 960]);
+    assert_eq!(slice.curves[0].ys, [2, 2, 3, 4]);
+    assert!(
+        project.voice_parts[0].slice(1200, 240).curves[0]
+            .xs
+            .is_empty()
+    );
+
+    let mut far = project.voice_parts[0].clone();
+    far.position = 2_000_000_000;
+    far.notes[0].position = 2_000_000_000;
+    let slice = far.slice(2_000_000_000, 2_100_000_000);
+    assert_eq!(slice.position, i32::MAX);
+    assert_eq!(slice.notes[0].position, 0);
+    let slice = far.slice(-2_000_000_000, i32::MAX);
+    assert_eq!(slice.position, 0);
+    assert_eq!(slice.notes[0].position, i32::MAX);
+}
+
+#[test]
+fn semantic_equality_ignores_cosmetic_fields() {
+    let original = Project::from_yaml_str(sample_yaml()).expect("parse");
+    let mut relabeled = original.clone();
+    relabeled.comment = String::from("edited");
+    relabeled.output_dir = String::from("Out");
+    relabeled.cache_dir = String::from("Cache");
+    relabeled.ustx_version = Some(CURRENT_VERSION);
+    assert_ne!(original, relabeled);
+    assert!(original.semantically_eq(&relabeled));
+
+    relabeled.key = 3;
+    assert!(!original.semantically_eq(&relabeled));
+
+    let mut upgraded = Project::from_yaml_str(sample_yaml()).expect("parse");
+    upgraded.tempos = Project::default().tempos;
+    upgraded.time_signatures = Project::default().time_signatures;
+    upgraded.exp_selectors = Project::default().exp_selectors;
+    let mut converted = upgraded.clone();
+    converted.convert_to(CURRENT_VERSION).expect("convert");
+    assert!(upgraded.semantically_eq(&converted));
+}
+
+#[test]
+fn inserts_tempos_in_order() {
+    let mut project = Project::default();
+    project.insert_tempo(Tempo {
+        position: 1920,
+        bpm: 90.0,
+    });
+    project.insert_tempo(Tempo {
+        position: 960,
+        bpm: 140.0,
+    });
+    project.insert_tempo(Tempo {
+        position: 1920,
+        bpm: 100.0,
+    });
+    let positions = project
+        .tempos
+        .iter()
+        .map(|tempo| tempo.position)
+        .collect::<Vec<_>>();
+    assert_eq!(positions, [0, 960, 1920]);
+    assert_eq!(project.tempo_at_tick(ustx::Ticks(0)), 120.0);
+    assert_eq!(project.tempo_at_tick(ustx::Ticks(1919)), 140.0);
+    assert_eq!(project.tempo_at_tick(ustx::Ticks(5000)), 100.0);
+
+    project.insert_time_signature(TimeSignature {
+        bar_position: 4,
+        beat_per_bar: 3,
+        beat_unit: 4,
+    });
+    project.insert_time_signature(TimeSignature {
+        bar_position: 0,
+        beat_per_bar: 6,
+        beat_unit: 8,
+    });
+    assert_eq!(project.time_signatures.len(), 2);
+    assert_eq!(project.time_signatures[0].beat_per_bar, 6);
+    assert_eq!(project.time_signatures[1].bar_position, 4);
+}
+
+#[test]
+fn strict_parse_rejects_unknown_keys() {
+    let project = Project::from_yaml_str_strict(sample_yaml()).expect("strict parse");
+    assert_eq!(project.name, "Demo");
+
+    let error = Project::from_yaml_str_strict("nmae: Typo\n").expect_err("unknown root key");
+    assert!(matches!(
+        error,
+        ustx::Error::UnknownField { ref field, ref location } if field == "nmae" && location == "."
+    ));
+
+    let error = Project::from_yaml_str_strict(
+        "voice_parts:\n- notes:\n  - tone: 60\n  - tone: 62\n    lyirc: a\n",
+    )
+    .expect_err("unknown nested key");
+    assert!(matches!(
+        error,
+        ustx::Error::UnknownField { ref field, ref location }
+            if field == "lyirc" && location == "voice_parts.0.notes.1"
+    ));
+    assert!(Project::from_yaml_str("nmae: Typo\n").is_ok());
+}
+
+#[test]
+fn pushes_notes_contiguously() {
+    let mut part = Project::from_yaml_str("voice_parts:\n- {}\n")
+        .expect("parse part")
+        .voice_parts
+        .remove(0);
+    for (tone, lyric) in [(60, "do"), (62, "re"), (64, "mi")] {
+        part.push_note_after(
+            Note::builder()
+                .tone(tone)
+                .lyric(lyric)
+                .duration(240)
+                .build(),
+        );
+    }
+    let positions = part
+        .notes
+        .iter()
+        .map(|note| note.position)
+        .collect::<Vec<_>>();
+    assert_eq!(positions, [0, 240, 480]);
+    assert_eq!(part.notes[2].lyric, "mi");
+
+    part.push_note_after(Note::builder().position(2000).build());
+    assert_eq!(part.notes[3].position, 2000);
+    assert_eq!(part.notes[3].duration, 120);
+    assert_eq!(part.notes[3].lyric, "あ");
+}
+
+#[test]
+fn converts_between_tones_and_frequencies() {
+    use ustx::pitch::{cents_between, hz_to_tone, tone_to_hz};
+
+    assert!((tone_to_hz(69) - 440.0).abs() < 1e-9);
+    assert!((tone_to_hz(81) - 880.0).abs() < 1e-9);
+    assert!((tone_to_hz(60) - 261.625_565).abs() < 1e-5);
+    assert!((hz_to_tone(220.0) - 57.0).abs() < 1e-9);
+    assert!((hz_to_tone(tone_to_hz(64)) - 64.0).abs() < 1e-9);
+    assert_eq!(cents_between(60, 67), 700.0);
+    assert_eq!(cents_between(67, 60), -700.0);
+
+    let point = ustx::PitchPoint {
+        x: 0.0,
+        y: 100.0,
+        shape: PitchPointShape::Io,
+    };
+    assert!((point.absolute_hz(68) - 440.0).abs() < 1e-9);
+}
+
+#[test]
+fn computes_statistics() {
+    let project = Project::from_yaml_str(
+        r"
+tracks:
+- {}
+- {}
+voice_parts:
+- track_no: 1
+  position: 480
+  notes:
+  - {position: 0, duration: 480, tone: 60, lyric: a}
+  - {position: 480, duration: 960, tone: 67, lyric: a}
+- track_no: 0
+  notes:
+  - {position: 0, duration: 240, tone: 55, lyric: i}
+wave_parts:
+- {}
+",
+    )
+    .expect("parse project");
+    let stats = project.statistics();
+    assert_eq!(stats.note_count, 3);
+    assert_eq!(stats.voice_part_count, 2);
+    assert_eq!(stats.wave_part_count, 1);
+    assert_eq!(stats.track_note_counts, [1, 2]);
+    assert_eq!(stats.distinct_lyrics, 2);
+    assert_eq!((stats.min_tone, stats.max_tone), (Some(55), Some(67)));
+    assert_eq!(stats.length_ticks, 1920);
+
+    let yaml = serde_yaml::to_string(&stats).expect("serialize stats");
+    assert!(yaml.contains("note_count: 3"));
+    assert_eq!(Project::default().statistics().length_ticks, 0);
+}
+
+#[cfg(feature = "ust")]
+#[test]
+fn imports_ust_notes() {
+    let ust = "[#VERSION]\nUST Version1.2\n[#SETTING]\nTempo=140.00\nProjectName=Song\n\
+        [#0000]\nLength=480\nLyric=ka\nNoteNum=60\n\
+        [#0001]\nLength=240\nLyric=R\nNoteNum=60\n\
+        [#0002]\nLength=960\nLyric=na\nNoteNum=62\nTempo=90\n[#TRACKEND]\n";
+    let project = Project::from_ust_str(ust).expect("import ust");
+    assert_eq!(project.name, "Song");
+    assert_eq!(project.tracks.len(), 1);
+    assert_eq!(project.voice_parts.len(), 1);
+    let notes = project.voice_parts[0]
+        .notes
+        .iter()
+        .map(|note| (note.position, note.duration, note.tone, note.lyric.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(notes, [(0, 480, 60, "ka"), (720, 960, 62, "na")]);
+    assert_eq!(project.tempo_at_tick(ustx::Ticks(0)), 140.0);
+    assert_eq!(project.tempo_at_tick(ustx::Ticks(720)), 90.0);
+
+    assert!(matches!(
+        Project::from_ust_str("[#0000]\nLyric=a\nNoteNum=60\n"),
+        Err(ustx::Error::Ust(_))
+    ));
+    assert!(matches!(
+        Project::from_ust_str("[#0000]\nLength=abc\n"),
+        Err(ustx::Error::Ust(_))
+    ));
+}
+
+#[test]
+fn normalize_enforces_invariants() {
+    let mut project = Project::from_yaml_str(
+        r"
+bpm: 150
+beat_per_bar: 3
+exp_selectors: [vel]
+exp_primary: -2
+exp_secondary: 40
+time_signatures: []
+tempos: []
+voice_parts:
+- notes:
+  - {position: 480, tone: 60}
+  - {position: 0, tone: 64}
+  - {position: 0, tone: 62}
+",
+    )
+    .expect("parse project");
+    project.normalize();
+    assert_eq!(project.tempos.len(), 1);
+    assert_eq!(project.tempos[0].bpm, 150.0);
+    assert_eq!(project.time_signatures[0].beat_per_bar, 3);
+    assert_eq!(project.exp_selectors.len(), 10);
+    assert_eq!(project.exp_selectors[0], "vel");
+    assert_eq!(project.exp_primary, 0);
+    assert_eq!(project.exp_secondary, 9);
+    let order = project.voice_parts[0]
+        .notes
+        .iter()
+        .map(|note| (note.position, note.tone))
+        .collect::<Vec<_>>();
+    assert_eq!(order, [(0, 62), (0, 64), (480, 60)]);
+}
+
+fn pitch_point(x: f32, y: f32, shape: PitchPointShape) -> ustx::PitchPoint {
+    ustx::PitchPoint { x, y, shape }
+}
+
+#[test]
+fn simplifies_pitch_points() {
+    let mut pitch = ustx::Pitch {
+        data: vec![
+            pitch_point(0.0, 0.0, PitchPointShape::L),
+            pitch_point(10.0, 10.0, PitchPointShape::L),
+            pitch_point(20.0, 20.5, PitchPointShape::L),
+            pitch_point(30.0, 30.0, PitchPointShape::L),
+            pitch_point(40.0, 0.0, PitchPointShape::L),
+            pitch_point(50.0, 0.0, PitchPointShape::L),
+        ],
+        snap_first: true,
+    };
+    pitch.simplify(1.0);
+    let xs = pitch.data.iter().map(|point| point.x).collect::<Vec<_>>();
+    assert_eq!(xs, [0.0, 30.0, 40.0, 50.0]);
+
+    let mut shaped = ustx::Pitch {
+        data: vec![
+            pitch_point(0.0, 0.0, PitchPointShape::Io),
+            pitch_point(10.0, 0.0, PitchPointShape::Io),
+            pitch_point(20.0, 0.0, PitchPointShape::L),
+            pitch_point(30.0, 0.0, PitchPointShape::L),
+            pitch_point(40.0, 0.0, PitchPointShape::L),
+        ],
+        snap_first: true,
+    };
+    shaped.simplify(1.0);
+    let xs = shaped.data.iter().map(|point| point.x).collect::<Vec<_>>();
+    assert_eq!(xs, [0.0, 10.0, 20.0, 40.0]);
+}
+
+#[test]
+fn thins_pitch_points() {
+    let mut note = Note::default();
+    note.pitch.data = (0..=100)
+        .map(|x| pitch_point(x as f32, (x * 2) as f32, PitchPointShape::L))
+        .collect();
+    assert_eq!(note.total_pitch_points(), 101);
+
+    note.pitch.thin_to(5);
+    assert_eq!(note.pitch.point_count(), 5);
+    let points = note
+        .pitch
+        .data
+        .iter()
+        .map(|point| (point.x, point.y))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        points,
+        [
+            (0.0, 0.0),
+            (25.0, 50.0),
+            (50.0, 100.0),
+            (75.0, 150.0),
+            (100.0, 200.0)
+        ]
+    );
+
+    let mut eased = ustx::Pitch {
+        data: vec![
+            pitch_point(0.0, 0.0, PitchPointShape::Io),
+            pitch_point(50.0, 100.0, PitchPointShape::Io),
+            pitch_point(100.0, 0.0, PitchPointShape::Io),
+        ],
+        snap_first: true,
+    };
+    assert!((eased.value_at(25.0) - 50.0).abs() < 1e-3);
+    eased.thin_to(2);
+    assert_eq!(eased.point_count(), 2);
+    eased.thin_to(10);
+    assert_eq!(eased.point_count(), 2);
+}
+
+#[test]
+fn snaps_first_pitch_point_to_previous_note() {
+    let mut pitch = ustx::Pitch {
+        data: vec![
+            pitch_point(-25.0, 0.0, PitchPointShape::Io),
+            pitch_point(25.0, 0.0, PitchPointShape::Io),
+        ],
+        snap_first: true,
+    };
+    let points = pitch.effective_points(62, Some(60));
+    assert_eq!(points[0].y, -200.0);
+    assert_eq!(points[1].y, 0.0);
+    assert_eq!(pitch.effective_points(62, None)[0].y, 0.0);
+
+    pitch.snap_first = false;
+    assert_eq!(pitch.effective_points(62, Some(60))[0].y, 0.0);
+}
+
+#[test]
+fn rescales_resolution() {
+    let mut project = Project::from_yaml_str(
+        r"
+resolution: 480
+tempos:
+- {position: 0, bpm: 120}
+- {position: 1920, bpm: 90}
+voice_parts:
+- position: 960
+  notes:
+  - position: 0
+    duration: 481
+    pitch:
+      data:
+      - {x: -24, y: 0}
+  curves:
+  - {abbr: dyn, xs: [0, 240], ys: [0, 10]}
+wave_parts:
+- position: 480
+",
+    )
+    .expect("parse project");
+    let report = project.set_resolution(960);
+    assert!(report.is_exact());
+    assert_eq!(project.resolution, 960);
+    assert_eq!(project.tempos[1].position, 3840);
+    assert_eq!(project.voice_parts[0].position, 1920);
+    assert_eq!(project.voice_parts[0].notes[0].duration, 962);
+    assert_eq!(project.voice_parts[0].notes[0].pitch.data[0].x, -48.0);
+    assert_eq!(project.voice_parts[0].curves[0].xs, [0, 480]);
+    assert_eq!(project.wave_parts[0].position, 960);
+
+    let report = project.set_resolution(480 * 3 / 4);
+    assert_eq!(
+        report.lossy,
+        [ustx::LossyTick {
+            field: "note.duration",
+            original: 962,
+            rounded: 361,
+        }]
+    );
+    assert_eq!(project.voice_parts[0].notes[0].duration, 361);
+}
+
+#[test]
+fn navigates_tracks_and_parts() {
+    let project = Project::from_yaml_str(
+        r"
+tracks:
+- {track_name: Lead}
+- {track_name: Harmony}
+voice_parts:
+- {name: a, track_no: 1}
+- {name: b, track_no: 0}
+- {name: c, track_no: 1}
+",
+    )
+    .expect("parse project");
+    assert_eq!(project[1].track_name, "Harmony");
+    assert_eq!(
+        project.track(0).map(|track| track.track_name.as_str()),
+        Some("Lead")
+    );
+    assert!(project.track(-1).is_none());
+    assert!(project.track(2).is_none());
+
+    let names = project[1]
+        .parts(&project)
+        .map(|part| part.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["a", "c"]);
+    let detached = ustx::Track::default();
+    assert_eq!(detached.parts(&project).count(), 0);
+}
+
+#[test]
+fn formats_yaml_in_openutau_style() {
+    let project = Project::from_yaml_str(
+        r"
+name: Formatted
+ustx_version: '0.6'
+bpm: 120.0
+expressions:
+  dyn: {name: dynamics (curve), abbr: dyn, type: curve, min: -240.0, max: 120.0}
+voice_parts:
+- notes:
+  - position: 0
+    duration: 480
+    lyric: a
+    pitch:
+      data:
+      - {x: -12.5, y: 0.0, shape: l}
+    phoneme_overrides:
+    - {index: 0, phoneme: ka}
+  curves:
+  - {abbr: dyn, xs: [0, 5], ys: [0, -10]}
+",
+    )
+    .expect("parse project");
+    let output = project.to_yaml_string_openutau_style().expect("serialize");
+    for line in [
+        "ustx_version: 0.6\n",
+        "bpm: 120\n",
+        "    type: Curve\n",
+        "    min: -240\n",
+        "      - x: -12.5\n",
+        "        y: 0\n",
+        "    - index: 0\n      phoneme: ka\n",
+        "  - xs:\n    - 0\n    - 5\n    ys:\n    - 0\n    - -10\n    abbr: dyn\n",
+    ] {
+        assert!(output.contains(line), "missing {line:?} in:\n{output}");
+    }
+    assert!(!output.contains("null"));
+    assert!(!output.contains("phoneme_indexes"));
+    let reparsed = Project::from_yaml_str(&output).expect("reparse");
+    assert_eq!(reparsed, project);
+}
+
+#[test]
+fn vibrato_presets_and_clearing() {
+    let mut note = Note::builder().build();
+    note.vibrato = Vibrato::preset_heavy();
+    assert!(note.vibrato.pitch_offset_at(1000.0, 500.0).abs() > 0.0);
+    assert!(Vibrato::preset_light().depth < Vibrato::preset_heavy().depth);
+
+    note.clear_vibrato();
+    assert_eq!(note.vibrato, Vibrato::none());
+    assert_eq!(note.vibrato.length, 0.0);
+    assert_eq!(note.vibrato.period, Vibrato::default().period);
+    assert_eq!(note.vibrato.pitch_offset_at(1000.0, 900.0), 0.0);
+}
+
+#[test]
+fn repairs_invalid_durations() {
+    let mut part = Project::from_yaml_str(
+        "voice_parts:\n- notes:\n  - {duration: 0}\n  - {duration: 480}\n  - {duration: -5}\n  - {duration: 10}\n",
+    )
+    .expect("parse notes")
+    .voice_parts
+    .remove(0);
+    assert_eq!(part.invalid_duration_notes(), [0, 2]);
+    assert_eq!(part.fix_durations(15), 3);
+    assert!(part.invalid_duration_notes().is_empty());
+    let durations = part
+        .notes
+        .iter()
+        .map(|note| note.duration)
+        .collect::<Vec<_>>();
+    assert_eq!(durations, [15, 480, 15, 15]);
+}
+
+#[test]
+fn computes_part_bounds() {
+    let project = Project::from_yaml_str(
+        r"
+voice_parts:
+- position: 1920
+  notes:
+  - {position: 480, duration: 240}
+  - {position: 120, duration: 240}
+- position: 960
+- position: 0
+  notes:
+  - {position: 0, duration: 1000}
+",
+    )
+    .expect("parse parts");
+    assert_eq!(project.voice_parts[0].tick_bounds(), Some((120, 720)));
+    assert_eq!(project.voice_parts[1].tick_bounds(), None);
+    assert_eq!(project.part_bounds(), [(0, 2040, 2640), (2, 0, 1000)]);
+
+    let far = Project::from_yaml_str(
+        "voice_parts:\n- position: 2147483000\n  notes:\n  - {position: 0, duration: 960}\n",
+    )
+    .expect("parse far part");
+    assert_eq!(far.part_bounds(), [(0, 2_147_483_000, i32::MAX)]);
+}
+
+#[test]
+fn finds_overlapping_parts() {
+    let project = Project::from_yaml_str(
+        r"
+bpm: 120
+resolution: 480
+voice_parts:
+- {track_no: 0, position: 0, notes: [{position: 0, duration: 960}]}
+- {track_no: 1, position: 480, notes: [{position: 0, duration: 960}]}
+- {track_no: 0, position: 960, notes: [{position: 0, duration: 480}]}
+- {track_no: 0, position: 480, notes: [{position: 0, duration: 960}]}
+wave_parts:
+- {track_no: 0, position: 0, file_duration_ms: 1000}
+- {track_no: 0, position: 960, file_duration_ms: 1000}
+- {track_no: 0, position: 900, file_duration_ms: 1000}
+",
+    )
+    .expect("parse parts");
+    assert_eq!(project.overlapping_parts(), [(0, 3), (2, 3)]);
+    assert_eq!(project.overlapping_wave_parts(), [(0, 2), (1, 2)]);
+
+    let huge = Project::from_yaml_str(
+        "wave_parts:\n- {position: 10, file_duration_ms: 1.0e12}\n- {position: 20, file_duration_ms: 1.0e12}\n",
+    )
+    .expect("parse huge wave parts");
+    assert_eq!(huge.overlapping_wave_parts(), [(0, 1)]);
+}
+
+#[test]
+fn finds_rests_in_unsorted_notes() {
+    let part = Project::from_yaml_str(
+        r"
+voice_parts:
+- notes:
+  - {position: 1440, duration: 480}
+  - {position: 240, duration: 480}
+  - {position: 720, duration: 240}
+",
+    )
+    .expect("parse notes")
+    .voice_parts
+    .remove(0);
+    assert_eq!(part.rests(), [(0, 240), (960, 480)]);
+    assert!(!part.is_continuous());
+
+    let legato = Project::from_yaml_str(
+        "voice_parts:\n- notes:\n  - {position: 480, duration: 480}\n  - {position: 120, duration: 360}\n",
+    )
+    .expect("parse notes")
+    .voice_parts
+    .remove(0);
+    assert_eq!(legato.rests(), [(0, 120)]);
+    assert!(legato.is_continuous());
+    assert!(ustx::VoicePart::default().is_continuous());
+}
+
+#[test]
+fn rounds_floats_when_serializing() {
+    let mut project = Project {
+        bpm: 120.123_456,
+        ..Project::default()
+    };
+    project.tracks[0].volume = 0.1;
+    let mut note = sample_note();
+    note.vibrato.depth = 33.333_33;
+    note.pitch
+        .data
+        .push(pitch_point(1.234_567, -2.5, PitchPointShape::L));
+    let mut part = ustx::VoicePart::default();
+    part.notes.push(note);
+    project.voice_parts.push(part);
+
+    let yaml = project
+        .to_yaml_string_rounded(2)
+        .expect("serialize rounded");
+    assert!(yaml.contains("bpm: 120.12\n"), "{yaml}");
+    assert!(yaml.contains("volume: 0.1\n"), "{yaml}");
+    assert!(yaml.contains("depth: 33.33\n"), "{yaml}");
+    assert!(yaml.contains("x: 1.23\n"), "{yaml}");
+    assert!(yaml.contains("y: -2.5\n"), "{yaml}");
+    assert!((project.bpm - 120.123_456).abs() < f64::EPSILON);
+}
+
+#[test]
+fn resolves_time_signatures_and_tempo_segments() {
+    let mut project = Project::default();
+    assert_eq!(project.tempo_segments(), [(0, i32::MAX, 120.0)]);
+    assert_eq!(project.time_signature_at(3).beat_per_bar, 4);
+
+    project.tempos = vec![
+        Tempo {
+            position: 480,
+            bpm: 100.0,
+        },
+        Tempo {
+            position: 1920,
+            bpm: 140.0,
+        },
+        Tempo {
+            position: 1920,
+            bpm: 150.0,
+        },
+    ];
+    assert_eq!(
+        project.tempo_segments(),
+        [(0, 1920, 100.0), (1920, i32::MAX, 150.0)]
+    );
+
+    project.time_signatures = vec![
+        TimeSignature {
+            bar_position: 2,
+            beat_per_bar: 3,
+            beat_unit: 4,
+        },
+        TimeSignature {
+            bar_position: 8,
+            beat_per_bar: 6,
+            beat_unit: 8,
+        },
+    ];
+    assert_eq!(project.time_signature_at(0).beat_per_bar, 3);
+    assert_eq!(project.time_signature_at(7).beat_per_bar, 3);
+    assert_eq!(project.time_signature_at(8).beat_unit, 8);
+}
+
+#[cfg(feature = "ron")]
+#[test]
+fn round_trips_through_ron() {
+    let mut project = Project::from_yaml_str_with_compat(sample_yaml()).expect("upgrade");
+    project.expressions.insert(
+        "dyn".into(),
+        descriptor(ExpressionType::Curve, -240.0, 120.0),
+    );
+    let mut note = sample_note();
+    note.pitch.data = vec![
+        pitch_point(-25.0, 0.0, PitchPointShape::Io),
+        pitch_point(0.0, 12.5, PitchPointShape::O),
+    ];
+    let mut part = ustx::VoicePart::default();
+    part.notes.push(note);
+    project.voice_parts.push(part);
+
+    let ron = project.to_ron_string().expect("serialize ron");
+    let parsed = Project::from_ron_str(&ron).expect("parse ron");
+    assert_eq!(parsed, project);
+    assert_eq!(parsed.ustx_version, Some(CURRENT_VERSION));
+    match Project::from_ron_str("(\n  name: 1,\n)") {
+        Err(ustx::Error::Ron(err)) => assert_eq!(err.span.start.line, 2),
+        other => panic!("expected a ron error, got {other:?}"),
+    }
+}
+
+#[test]
+fn groups_connector_notes_into_syllables() {
+    let part = Project::from_yaml_str(
+        r"
+voice_parts:
+- notes:
+  - {position: 0, lyric: la}
+  - {position: 480, lyric: +}
+  - {position: 960, lyric: '...a'}
+  - {position: 1440, lyric: li}
+  - {position: 1920, lyric: lu}
+",
+    )
+    .expect("parse notes")
+    .voice_parts
+    .remove(0);
+    assert_eq!(part.syllables(), [0..3, 3..4, 4..5]);
+    assert!(ustx::VoicePart::default().syllables().is_empty());
+
+    assert!(part.notes[2].is_connector());
+    let mut mapped = part.clone();
+    mapped.map_lyrics(str::to_uppercase);
+    let lyrics = mapped
+        .notes
+        .iter()
+        .map(|note| note.lyric.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(lyrics, ["LA", "+", "...a", "LI", "LU"]);
+}
+
+#[test]
+fn resolves_expression_values() {
+    let mut project = Project::default();
+    project.expressions.insert(
+        "vel".into(),
+        ExpressionDescriptor {
+            default_value: 100.0,
+            ..descriptor(ExpressionType::Numerical, 0.0, 200.0)
+        },
+    );
+    assert_eq!(project.effective_expression("vel", Some(80.0)), Some(80.0));
+    assert_eq!(project.effective_expression("vel", None), Some(100.0));
+    assert_eq!(project.effective_expression("xyz", None), None);
+
+    let mut note = sample_note();
+    assert_eq!(note.expression_value("vel", &project), Some(100.0));
+    note.phoneme_expressions
+        .push(Expression::numerical("vel", 150.0));
+    assert_eq!(note.expression_value("vel", &project), Some(150.0));
+    assert_eq!(note.expression_value("xyz", &project), None);
+}
+
+#[test]
+fn remaps_selected_expressions() {
+    let mut project = Project {
+        exp_selectors: ["dyn", "pitd", "clr", "eng"].map(String::from).to_vec(),
+        exp_primary: 1,
+        exp_secondary: 3,
+        ..Project::default()
+    };
+    project.set_exp_selectors(["eng", "vel", "pitd"].map(String::from).to_vec());
+    assert_eq!(project.exp_primary, 2);
+    assert_eq!(project.exp_secondary, 0);
+
+    project.set_exp_selectors(vec![String::from("clr")]);
+    assert_eq!(project.exp_selectors, ["clr"]);
+    assert_eq!((project.exp_primary, project.exp_secondary), (0, 0));
+}
+
+#[test]
+fn ignores_byte_order_mark_and_rejects_invalid_utf8() {
+    let with_bom = format!("\u{feff}{}", sample_yaml());
+    assert_eq!(
+        Project::from_yaml_str(&with_bom)
+            .expect("parse with bom")
+            .name,
+        "Demo"
+    );
+    let upgraded = Project::from_yaml_str_with_compat(&with_bom).expect("upgrade with bom");
+    assert_eq!(upgraded.comment, "sample");
+    let read = Project::from_reader(with_bom.as_bytes()).expect("read with bom");
+    assert_eq!(read.name, "Demo");
+
+    let invalid: &[u8] = b"name: \xff\xfe\n";
+    assert!(matches!(
+        Project::from_reader(invalid),
+        Err(ustx::Error::Encoding(_))
+    ));
+}
+
+#[test]
+fn converts_wave_part_duration_through_tempo_map() {
+    let project = Project::from_yaml_str(
+        r"
+resolution: 480
+tempos:
+- {position: 0, bpm: 120}
+- {position: 1920, bpm: 60}
+wave_parts:
+- {position: 960, file_duration_ms: 3000, skip_ms: 500, trim_ms: 500}
+",
+    )
+    .expect("parse wave part");
+    assert!((project.tick_to_ms(ustx::Ticks(1920)).0 - 2000.0).abs() < 1e-9);
+    assert!((project.ms_to_tick(ustx::Millis(3000.0)).0 - 2400.0).abs() < 1e-9);
+
+    let part = &project.wave_parts[0];
+    assert!((part.effective_duration_ms().0 - 2000.0).abs() < f64::EPSILON);
+    assert_eq!(part.duration_ticks(&project), ustx::Ticks(1440));
+}
+
+#[test]
+fn clamps_and_validates_track_levels() {
+    let mut project =
+        Project::from_yaml_str("tracks:\n- {volume: 20.5, pan: -0.5}\n- {volume: -3, pan: 1.5}\n")
+            .expect("parse tracks");
+    assert_eq!(
+        project.validate(),
+        [
+            ValidationIssue::VolumeOutOfRange {
+                track_index: 0,
+                volume: 20.5
+            },
+            ValidationIssue::PanOutOfRange {
+                track_index: 1,
+                pan: 1.5
+            },
+        ]
+    );
+    assert_eq!(project.tracks[0].normalized_volume(), 12.0);
+    assert_eq!(project.tracks[1].normalized_pan(), 1.0);
+
+    for track in &mut project.tracks {
+        track.clamp_levels();
+    }
+    assert!(project.validate().is_empty());
+    assert_eq!(project.tracks[1].volume, -3.0);
+}
+
+#[test]
+fn applies_phoneme_overrides() {
+    let mut note = sample_note();
+    note.phoneme_overrides = vec![
+        PhonemeOverride {
+            index: 1,
+            phoneme: Some(String::from("a")),
+            preutter_delta: Some(5.0),
+            ..PhonemeOverride::default()
+        },
+        PhonemeOverride {
+            index: 0,
+            offset: Some(-30),
+            overlap_delta: Some(-2.5),
+            ..PhonemeOverride::default()
+        },
+        PhonemeOverride {
+            index: 7,
+            phoneme: Some(String::from("x")),
+            ..PhonemeOverride::default()
+        },
+    ];
+    let mut phonemes = vec![
+        Phoneme {
+            phoneme: String::from("k"),
+            offset: -60,
+            preutter: 20.0,
+            overlap: 10.0,
+        },
+        Phoneme {
+            phoneme: String::from("o"),
+            offset: 0,
+            preutter: 10.0,
+            overlap: 5.0,
+        },
+    ];
+    note.apply_overrides(&mut phonemes);
+    assert_eq!(phonemes[0].phoneme, "k");
+    assert_eq!(phonemes[0].offset, -30);
+    assert_eq!(phonemes[0].overlap, 7.5);
+    assert_eq!(phonemes[1].phoneme, "a");
+    assert_eq!(phonemes[1].preutter, 15.0);
+}
+
+#[test]
+fn adds_expressions_with_validation() {
+    let mut project = Project::default();
+    let mut volume = descriptor(ExpressionType::Numerical, 0.0, 200.0);
+    volume.abbr = String::from("vol");
+    volume.default_value = 100.0;
+    project.add_expression(volume.clone()).expect("add volume");
+    assert_eq!(project.expressions["vol"], volume);
+    assert!(matches!(
+        project.add_expression(volume),
+        Err(ustx::Error::InvalidExpression(_))
+    ));
+
+    let out_of_range = ExpressionDescriptor {
+        default_value: 300.0,
+        ..descriptor(ExpressionType::Numerical, 0.0, 200.0)
+    };
+    assert!(project.add_expression(out_of_range).is_err());
+    let no_options = descriptor(ExpressionType::Options, 0.0, 0.0);
+    assert!(project.add_expression(no_options).is_err());
+    assert!(!project.expressions.contains_key("tst"));
+}
+
+#[test]
+fn measures_project_length_including_wave_parts() {
+    assert_eq!(Project::default().length_ticks(), ustx::Ticks(0));
+    assert_eq!(Project::default().length_ms(), ustx::Millis(0.0));
+
+    let mut project = Project::from_yaml_str(
+        r"
+resolution: 480
+tempos:
+- {position: 0, bpm: 120}
+voice_parts:
+- position: 480
+  notes:
+  - {position: 0, duration: 480}
+  - {position: 480, duration: 960}
+wave_parts:
+- {position: 0, file_duration_ms: 2500}
+",
+    )
+    .expect("parse project");
+    assert_eq!(project.length_ticks(), ustx::Ticks(2400));
+    assert!((project.length_ms().0 - 2500.0).abs() < 1e-9);
+    assert_eq!(project.statistics().length_ticks, 1920);
+
+    project.wave_parts.clear();
+    assert_eq!(project.length_ticks(), ustx::Ticks(1920));
+
+    let huge = Project::from_yaml_str("wave_parts:\n- {position: 10, file_duration_ms: 1.0e12}\n")
+        .expect("parse huge wave part");
+    assert_eq!(huge.length_ticks(), ustx::Ticks(i32::MAX));
+}
+
+#[test]
+fn preserves_version_spelling_until_changed() {
+    let input = sample_yaml().replace("ustx_version: \"0.3\"", "ustx_version: 0.3.0");
+    let mut project = Project::from_yaml_str(&input).expect("parse");
+    let version = project.ustx_version.expect("version");
+    assert_eq!(version, ustx::Version::new(0, 3, 0));
+    assert_eq!(project, project.clone().with_version(version));
+    let yaml = project.to_yaml_string().expect("serialize");
+    assert!(yaml.contains("ustx_version: 0.3.0\n"), "{yaml}");
+
+    let mut edited = project.clone();
+    edited.ustx_version = Some(ustx::Version::new(0, 4, 0));
+    let yaml = edited.to_yaml_string().expect("serialize");
+    assert!(yaml.contains("ustx_version: '0.4'\n"), "{yaml}");
+
+    project
+        .convert_to(ustx::Version::new(0, 3, 0))
+        .expect("no-op");
+    assert!(
+        project
+            .to_yaml_string()
+            .expect("serialize")
+            .contains("0.3.0")
+    );
+
+    project.convert_to(CURRENT_VERSION).expect("upgrade");
+    let yaml = project.to_yaml_string().expect("serialize");
+    assert!(yaml.contains("ustx_version: '0.7'\n"), "{yaml}");
+
+    let spelled = "1".parse::<ustx::Version>().expect("parse version");
+    assert_eq!(spelled, ustx::Version::new(1, 0, 0));
+    assert_eq!(serde_yaml::to_string(&spelled).expect("serialize"), "'1'\n");
+    let mut bumped = spelled;
+    bumped.minor = 2;
+    assert_eq!(
+        serde_yaml::to_string(&bumped).expect("serialize"),
+        "'1.2'\n"
+    );
+}
+
+#[test]
+fn converts_every_project_in_a_directory() {
+    let dir = std::env::temp_dir().join(format!("ustx-convert-dir-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).expect("create temp dir");
+    let current = Project::from_yaml_str_with_compat(sample_yaml())
+        .expect("upgrade")
+        .to_yaml_string()
+        .expect("serialize");
+    std::fs::write(dir.join("a_old.ustx"), sample_yaml()).expect("write old");
+    std::fs::write(dir.join("b_current.ustx"), &current).expect("write current");
+    std::fs::write(dir.join("c_broken.ustx"), "tracks: 3\n").expect("write broken");
+    std::fs::write(dir.join("notes.txt"), "not a project").expect("write other");
+
+    let outcomes = ustx::convert_dir(&dir, CURRENT_VERSION).expect("convert dir");
+    let names = outcomes
+        .iter()
+        .map(|(path, _)| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or("")
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["a_old.ustx", "b_current.ustx", "c_broken.ustx"]);
+    assert!(matches!(outcomes[0].1, ustx::ConvertOutcome::Converted));
+    assert!(matches!(outcomes[1].1, ustx::ConvertOutcome::Unchanged));
+    assert!(matches!(outcomes[2].1, ustx::ConvertOutcome::Failed(_)));
+
+    let converted = std::fs::read_to_string(dir.join("a_old.ustx")).expect("read converted");
+    assert_eq!(
+        Project::from_yaml_str(&converted)
+            .expect("parse converted")
+            .ustx_version,
+        Some(CURRENT_VERSION)
+    );
+    assert_eq!(
+        std::fs::read_to_string(dir.join("b_current.ustx")).expect("read current"),
+        current
+    );
+    let mut leftovers = std::fs::read_dir(&dir)
+        .expect("read temp dir")
+        .map(|entry| entry.expect("entry").file_name())
+        .collect::<Vec<_>>();
+    leftovers.sort();
+    assert_eq!(
+        leftovers,
+        ["a_old.ustx", "b_current.ustx", "c_broken.ustx", "notes.txt"]
+    );
+    std::fs::remove_dir_all(&dir).expect("remove temp dir");
+}
+
+fn serialized_keys<T: serde::Serialize>(value: &T) -> Vec<String> {
+    serde_yaml::to_value(value)
+        .expect("serialize value")
+        .as_mapping()
+        .expect("mapping")
+        .keys()
+        .filter_map(|key| key.as_str().map(String::from))
+        .collect()
+}
+
+#[test]
+fn field_names_match_serialized_keys() {
+    let project = Project {
+        ustx_version: Some(CURRENT_VERSION),
+        ..Project::default()
+    };
+    assert_eq!(serialized_keys(&project), Project::field_names());
+    assert_eq!(
+        serialized_keys(&ustx::Track::default()),
+        ustx::Track::field_names()
+    );
+    assert_eq!(serialized_keys(&Note::default()), Note::field_names());
+}
+
+#[test]
+fn removes_track_and_reindexes_parts() {
+    let mut project = Project::from_yaml_str(
+        r"
+tracks:
+- {track_name: Lead}
+- {track_name: Harmony}
+- {track_name: Bass}
+voice_parts:
+- {name: lead, track_no: 0}
+- {name: harmony, track_no: 1}
+- {name: bass, track_no: 2}
+wave_parts:
+- {name: backing, track_no: 2}
+- {name: guide, track_no: 1}
+",
+    )
+    .expect("parse project");
+    assert!(project.remove_track(3).is_none());
+
+    let removed = project.remove_track(1).expect("remove harmony");
+    assert_eq!(removed.track.track_name, "Harmony");
+    assert_eq!(removed.voice_parts[0].name, "harmony");
+    assert_eq!(removed.wave_parts[0].name, "guide");
+    assert_eq!(project.tracks.len(), 2);
+    let voice = project
+        .voice_parts
+        .iter()
+        .map(|part| (part.name.as_str(), part.track_no))
+        .collect::<Vec<_>>();
+    assert_eq!(voice, [("lead", 0), ("bass", 1)]);
+    assert_eq!(project.wave_parts.len(), 1);
+    assert_eq!(project.wave_parts[0].track_no, 1);
+}
+
+#[test]
+fn duplicates_track_with_its_parts() {
+    let mut project = Project::from_yaml_str(
+        r"
+tracks:
+- {track_name: Lead}
+- {track_name: Bass}
+voice_parts:
+- name: lead
+  track_no: 0
+  notes:
+  - {position: 0, lyric: la}
+- {name: bass, track_no: 1}
+wave_parts:
+- {name: guide, track_no: 0}
+",
+    )
+    .expect("parse project");
+    assert_eq!(project.duplicate_track(5), None);
+    assert_eq!(project.duplicate_track(0), Some(2));
+    assert_eq!(project.tracks[2].track_name, "Lead");
+    assert_eq!(project.voice_parts.len(), 3);
+    assert_eq!(project.voice_parts[2].name, "lead");
+    assert_eq!(project.voice_parts[2].track_no, 2);
+    assert_eq!(project.voice_parts[2].notes, project.voice_parts[0].notes);
+    assert_eq!(project.voice_parts[0].track_no, 0);
+    assert_eq!(project.wave_parts[1].track_no, 2);
+    assert_eq!(project.tracks[2].parts(&project).count(), 1);
+}
+
+#[test]
+fn display_does_not_upgrade_version() {
+    let project = Project::from_yaml_str(sample_yaml()).expect("parse");
+    let displayed = project.to_string();
+    assert!(displayed.contains("ustx_version: '0.3'\n"), "{displayed}");
+    assert!(displayed.contains("tempos: []\n"), "{displayed}");
+
+    let upgraded = project.to_yaml_string_with_compat().expect("upgrade");
+    assert!(upgraded.contains("ustx_version: '0.7'\n"), "{upgraded}");
+}
+
+#[test]
+fn converts_tones_to_and_from_names() {
+    let mut note = sample_note();
+    assert_eq!(note.tone_name(), "C4");
+    for (name, tone) in [
+        ("A4", 69),
+        ("a#3", 58),
+        ("Bb3", 58),
+        ("B#3", 60),
+        ("Cb4", 59),
+    ] {
+        note.set_tone_from_name(name).expect("parse tone name");
+        assert_eq!(note.tone, tone, "{name}");
+    }
+    note.set_tone_from_name("C-1").expect("parse lowest octave");
+    assert_eq!(note.tone, 0);
+    note.tone = -1;
+    assert_eq!(note.tone_name(), "B-2");
+
+    for invalid in ["", "H4", "C", "C#x", "4C"] {
+        assert!(note.set_tone_from_name(invalid).is_err(), "{invalid}");
+    }
+    assert_eq!(note.tone, -1);
+
+    assert_eq!(ustx::pitch::tone_name(60, 3), "C3");
+    assert_eq!(ustx::pitch::parse_tone_name("C3", 3).expect("parse"), 60);
+}
+
+#[test]
+fn reconciles_expression_indexes_with_selectors() {
+    let selectors = ["dyn", "pitd", "clr"].map(String::from);
+    let mut expression = Expression {
+        index: Some(0),
+        abbr: String::from("clr"),
+        value: 1.0,
+    };
+    expression.sync_index(&selectors);
+    assert_eq!(expression.index, Some(2));
+    expression.index = Some(1);
+    expression.sync_abbr(&selectors);
+    assert_eq!(expression.abbr, "pitd");
+    expression.index = Some(9);
+    expression.sync_abbr(&selectors);
+    assert_eq!(expression.abbr, "pitd");
+
+    let mut project = Project {
+        exp_selectors: selectors.to_vec(),
+        ..Project::default()
+    };
+    project.tracks[0]
+        .track_expressions
+        .push(Expression::numerical("pitd", 0.0));
+    let mut note = sample_note();
+    note.phoneme_expressions = vec![
+        Expression {
+            index: Some(0),
+            ..Expression::numerical("clr", 0.0)
+        },
+        Expression {
+            index: Some(1),
+            ..Expression::numerical("vel", 0.0)
+        },
+    ];
+    let mut part = ustx::VoicePart::default();
+    part.notes.push(note);
+    project.voice_parts.push(part);
+
+    project.reconcile_expression_indexes();
+    assert_eq!(project.tracks[0].track_expressions[0].index, Some(1));
+    let indexes = project.voice_parts[0].notes[0]
+        .phoneme_expressions
+        .iter()
+        .map(|expression| expression.index)
+        .collect::<Vec<_>>();
+    assert_eq!(indexes, [Some(2), None]);
+}
+
+#[test]
+fn lossy_parse_recovers_valid_data() {
+    let (project, errors) = Project::from_yaml_str_lossy(
+        r"
+name: Rescued
+bpm: fast
+tracks:
+- {track_name: Lead}
+- {track_name: Broken, volume: loud}
+voice_parts:
+- name: verse
+  notes:
+  - {position: 0, lyric: la}
+  - {position: 480, tone: high}
+  - {position: 960, lyric: li}
+",
+    );
+    let project = project.expect("recovered project");
+    assert_eq!(errors.len(), 3, "{errors:?}");
+    assert_eq!(project.name, "Rescued");
+    assert_eq!(project.bpm, 120.0);
+    assert_eq!(project.tracks.len(), 1);
+    let lyrics = project
+        .notes()
+        .map(|note| note.lyric.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(lyrics, ["la", "li"]);
+
+    let (project, errors) = Project::from_yaml_str_lossy("- not a project\n");
+    assert!(project.is_none());
+    assert_eq!(errors.len(), 1);
+    let (project, errors) = Project::from_yaml_str_lossy(sample_yaml());
+    assert!(project.is_some() && errors.is_empty());
+}
+
+#[test]
+fn leaf_types_support_hashing() {
+    use std::collections::HashSet;
+
+    let shapes = [PitchPointShape::Io, PitchPointShape::L, PitchPointShape::Io]
+        .into_iter()
+        .collect::<HashSet<_>>();
+    assert_eq!(shapes.len(), 2);
+
+    let kinds = [ExpressionType::Curve, ExpressionType::Curve]
+        .into_iter()
+        .collect::<HashSet<_>>();
+    assert_eq!(kinds.len(), 1);
+
+    let signatures = [TimeSignature::default(), TimeSignature::default()]
+        .into_iter()
+        .collect::<HashSet<_>>();
+    assert_eq!(signatures.len(), 1);
+
+    let versions = ["0.3", "0.3.0", "0.4"]
+        .into_iter()
+        .map(|raw| raw.parse::<ustx::Version>().expect("parse version"))
+        .collect::<HashSet<_>>();
+    assert_eq!(versions.len(), 2);
+
+    assert_eq!(
+        PhonemeOverride::default(),
+        PhonemeOverride {
+            index: 0,
+            ..PhonemeOverride::default()
+        }
+    );
+    assert_eq!(
+        Tempo::default(),
+        Tempo {
+            position: 0,
+            bpm: 120.0
+        }
+    );
+}
+
+#[test]
+fn seeds_missing_standard_expressions() {
+    let mut project = Project::default();
+    project.expressions.insert(
+        String::from("vel"),
+        descriptor(ExpressionType::Numerical, 0.0, 50.0),
+    );
+    let missing = project.missing_standard_expressions();
+    assert_eq!(missing.len(), 9);
+    assert!(!missing.contains(&"vel"));
+
+    project.add_standard_expressions();
+    assert!(project.missing_standard_expressions().is_empty());
+    assert_eq!(project.expressions["vel"].max, 50.0);
+    let dynamics = &project.expressions["dyn"];
+    assert_eq!(dynamics.r#type, ExpressionType::Curve);
+    assert_eq!((dynamics.min, dynamics.max), (-240.0, 120.0));
+    assert_eq!(project.expressions["gen"].flag.as_deref(), Some("g"));
+    assert!(ExpressionDescriptor::standard("xyz").is_none());
+}
+
+#[cfg(feature = "audio")]
+fn write_wav(path: &Path, sample_rate: u32, samples: &[i16]) {
+    let data_len = u32::try_from(samples.len() * 2).expect("data length");
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(b"RIFF");
+    bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+    bytes.extend_from_slice(b"WAVEfmt ");
+    bytes.extend_from_slice(&16_u32.to_le_bytes());
+    bytes.extend_from_slice(&1_u16.to_le_bytes());
+    bytes.extend_from_slice(&1_u16.to_le_bytes());
+    bytes.extend_from_slice(&sample_rate.to_le_bytes());
+    bytes.extend_from_slice(&(sample_rate * 2).to_le_bytes());
+    bytes.extend_from_slice(&2_u16.to_le_bytes());
+    bytes.extend_from_slice(&16_u16.to_le_bytes());
+    bytes.extend_from_slice(b"data");
+    bytes.extend_from_slice(&data_len.to_le_bytes());
+    for sample in samples {
+        bytes.extend_from_slice(&sample.to_le_bytes());
+    }
+    std::fs::write(path, bytes).expect("write wav");
+}
+
+#[cfg(feature = "audio")]
+#[test]
+fn computes_wave_part_peaks() {
+    let dir = std::env::temp_dir().join(format!("ustx-peaks-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).expect("create temp dir");
+    write_wav(
+        &dir.join("vocal.wav"),
+        1000,
+        &[0, 16384, -16384, 32767, i16::MIN, 0, 8192, -8192, 0, 0],
+    );
+    let project = Project::from_yaml_str(
+        "wave_parts:\n- {relative_path: vocal.wav, file_duration_ms: 10, skip_ms: 2, trim_ms: 2}\n",
+    )
+    .expect("parse wave part");
+    let part = &project.wave_parts[0];
+
+    let peaks = part.compute_peaks(&dir, 3).expect("compute peaks");
+    assert_eq!(peaks.len(), 3);
+    assert_eq!(peaks[0].0, -0.5);
+    assert!((peaks[0].1 - 1.0).abs() < 1e-4);
+    assert_eq!(peaks[1], (-1.0, 0.0));
+    assert_eq!(peaks[2], (-0.25, 0.25));
+    assert!(part.compute_peaks(&dir, 0).expect("no buckets").is_empty());
+    let fine = part.compute_peaks(&dir, 8).expect("compute fine peaks");
+    assert_eq!(fine.len(), 8);
+    assert_eq!(fine[0], (0.0, 0.0));
+    assert_eq!(fine[1], (-0.5, -0.5));
+    assert_eq!(fine[4], (0.0, 0.0));
+    assert_eq!(fine[7], (-0.25, -0.25));
+
+    std::fs::write(dir.join("notes.wav"), "not audio").expect("write text");
+    let mut other = part.clone();
+    other.relative_path = String::from("notes.wav");
+    assert!(matches!(
+        other.compute_peaks(&dir, 4),
+        Err(ustx::Error::Audio(_))
+    ));
+    other.relative_path = String::from("missing.wav");
+    assert!(matches!(
+        other.compute_peaks(&dir, 4),
+        Err(ustx::Error::Audio(_))
+    ));
+    std::fs::remove_dir_all(&dir).expect("remove temp dir");
+}
+
+#[test]
+fn splits_part_at_tick() {
+    let part = Project::from_yaml_str(
+        r"
+voice_parts:
+- name: verse
+  position: 1920
+  notes:
+  - {position: 0, duration: 480, lyric: a}
+  - {position: 720, duration: 480, lyric: b}
+  - {position: 960, duration: 480, lyric: c}
+  curves:
+  - {abbr: dyn, xs: [0, 900, 960, 1200], ys: [0, 10, 20, 30]}
+",
+    )
+    .expect("parse part")
+    .voice_parts
+    .remove(0);
+    let (left, right) = part.clone().split_at(930);
+    assert_eq!(left.curves[0].xs, [0, 900, 930]);
+    assert_eq!(left.curves[0].ys, [0, 10, 15]);
+    assert_eq!(right.curves[0].xs, [0, 30, 270]);
+    assert_eq!(right.curves[0].ys, [15, 20, 30]);
+
+    let mut far = part.clone();
+    far.position = 2_000_000_000;
+    far.notes[2].position = 2_000_000_000;
+    let (_, right) = far.split_at(2_000_000_000);
+    assert_eq!(right.position, i32::MAX);
+    assert_eq!(right.notes[0].position, 0);
+
+    let (left, right) = part.split_at(960);
+
+    assert_eq!(left.position, 1920);
+    let left_lyrics = left
+        .notes
+        .iter()
+        .map(|note| note.lyric.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(left_lyrics, ["a", "b"]);
+    assert_eq!(left.notes[1].duration, 480);
+    assert_eq!(left.curves[0].xs, [0, 900, 960]);
+    assert_eq!(left.curves[0].ys, [0, 10, 20]);
+
+    assert_eq!(right.position, 2880);
+    assert_eq!(right.name, "verse");
+    assert_eq!(right.notes.len(), 1);
+    assert_eq!(
+        (right.notes[0].position, right.notes[0].lyric.as_str()),
+        (0, "c")
+    );
+    assert_eq!(right.curves[0].abbr, "dyn");
+    assert_eq!(right.curves[0].xs, [0, 240]);
+    assert_eq!(right.curves[0].ys, [20, 30]);
+}
+
+#[test]
+fn resolves_singer_voicebank_directories() {
+    let root = std::env::temp_dir().join(format!("ustx-singers-{}", std::process::id()));
+    for (dir, character) in [
+        ("teto", Some("name: Kasane Teto\nimage: teto.png\n")),
+        ("broken", Some(": : :\n")),
+        ("plain", None),
+    ] {
+        std::fs::create_dir_all(root.join(dir)).expect("create voicebank dir");
+        if let Some(character) = character {
+            std::fs::write(root.join(dir).join("character.yaml"), character)
+                .expect("write character.yaml");
+        }
+    }
+
+    let mut project = Project::from_yaml_str("tracks:\n- {singer: Kasane Teto}\n- {singer: ''}\n")
+        .expect("parse tracks");
+    assert_eq!(project.tracks[0].singer_dir(&root), Some(root.join("teto")));
+    assert_eq!(project.tracks[1].singer_dir(&root), None);
+    assert_eq!(
+        project.singer_dirs(&root).expect("resolve singers"),
+        [Some(root.join("teto")), None]
+    );
+
+    project.tracks[1].singer = String::from("Missing");
+    assert!(matches!(
+        project.singer_dirs(&root),
+        Err(ustx::Error::SingerNotFound(singer)) if singer == "Missing"
+    ));
+    std::fs::remove_dir_all(&root).expect("remove temp dir");
+}
+
+#[test]
+fn maps_parts_and_notes_in_storage_order() {
+    let mut project = Project::from_yaml_str(
+        r"
+voice_parts:
+- notes:
+  - {position: 480, lyric: b}
+  - {position: 0, lyric: a}
+- notes:
+  - {position: 0, lyric: c}
+",
+    )
+    .expect("parse parts");
+    let mut visited = Vec::new();
+    project.map_notes(|note| {
+        visited.push(note.lyric.clone());
+        note.lyric = note.lyric.to_uppercase();
+    });
+    assert_eq!(visited, ["b", "a", "c"]);
+    assert_eq!(project.voice_parts[0].notes[1].lyric, "A");
+
+    project.map_voice_parts(|part| part.track_no = 1);
+    assert!(project.voice_parts.iter().all(|part| part.track_no == 1));
+}
+
+#[test]
+fn rejects_non_positive_resolution() {
+    let input = sample_yaml().replace("resolution: 480", "resolution: 0");
+    assert!(matches!(
+        Project::from_yaml_str_with_compat(&input),
+        Err(ustx::Error::InvalidResolution(0))
+    ));
+    let project = Project::from_yaml_str(&input).expect("raw parse");
+    assert_eq!(project.validate(), [ValidationIssue::InvalidResolution(0)]);
+    assert!(Project::default().validate().is_empty());
+}
+
+#[test]
+fn rebases_wave_part_paths() {
+    let mut project = Project::from_yaml_str(
+        r"
+output_dir: Render\Vocal
+wave_parts:
+- {relative_path: 'audio\vocal.wav'}
+- {relative_path: ../shared/drums.wav}
+- {relative_path: ./take.wav}
+",
+    )
+    .expect("parse wave parts");
+    project.rebase_paths(Path::new("/music/song"), Path::new("/music/archive/song"));
+    let paths = project
+        .wave_parts
+        .iter()
+        .map(|part| part.relative_path.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        paths,
+        [
+            "../../song/audio/vocal.wav",
+            "../../shared/drums.wav",
+            "../../song/take.wav"
+        ]
+    );
+    assert_eq!(project.output_dir, "Render/Vocal");
+
+    project.rebase_paths(Path::new("/music/archive/song"), Path::new("/music/song"));
+    assert_eq!(project.wave_parts[0].relative_path, "audio/vocal.wav");
+    assert_eq!(project.wave_parts[1].relative_path, "../shared/drums.wav");
+
+    project.rebase_paths(Path::new("song"), Path::new("archive/song"));
+    assert_eq!(
+        project.wave_parts[0].relative_path,
+        "../../song/audio/vocal.wav"
+    );
+    assert_eq!(
+        project.wave_parts[1].relative_path,
+        "../../shared/drums.wav"
+    );
+    project.rebase_paths(Path::new("archive/song"), Path::new("../other"));
+    let current = std::env::current_dir().expect("current dir");
+    let name = current
+        .file_name()
+        .expect("current dir name")
+        .to_string_lossy();
+    assert_eq!(
+        project.wave_parts[0].relative_path,
+        format!("../{name}/song/audio/vocal.wav")
+    );
+    assert_eq!(
+        project.wave_parts[1].relative_path,
+        format!("../{name}/shared/drums.wav")
+    );
+}
+
+#[test]
+fn streams_notes_from_yaml() {
+    let input = r"
+name: Big
+voice_parts:
+- name: first
+  curves: [{abbr: dyn, xs: [0], ys: [0]}]
+  notes:
+  - {position: 0, lyric: a, pitch: {data: [{x: 0, y: 0, shape: io}]}}
+  - {position: 480, lyric: b}
+- name: empty
+- name: second
+  notes:
+  - {position: 0, lyric: c}
+tracks: [{track_name: Lead}]
+";
+    let mut lyrics = Vec::new();
+    Project::for_each_note_in_yaml(input, |note| lyrics.push(note.lyric.clone()))
+        .expect("stream notes");
+    assert_eq!(lyrics, ["a", "b", "c"]);
+    assert_eq!(
+        Project::count_notes_from_yaml(input).expect("count notes"),
+        3
+    );
+    assert_eq!(
+        Project::count_notes_from_yaml(sample_yaml()).expect("count"),
+        0
+    );
+    assert!(Project::count_notes_from_yaml("voice_parts:\n- notes:\n  - tone: high\n").is_err());
+}
+
+#[test]
+fn vibrato_length_is_a_percentage_of_the_note() {
+    let vibrato = Vibrato {
+        length: 75.0,
+        ..Vibrato::default()
+    };
+    assert_eq!(vibrato.length_ms(1000.0), 750.0);
+    assert_eq!(Vibrato::none().length_ms(1000.0), 0.0);
+    let overlong = Vibrato {
+        length: 150.0,
+        ..Vibrato::default()
+    };
+    assert_eq!(overlong.length_ms(400.0), 400.0);
+}
+
+#[test]
+fn reads_pitch_deviation_curve_points() {
+    let input = r"
+voice_parts:
+- curves:
+  - {abbr: dyn, xs: [0], ys: [5]}
+  - {abbr: pitd, xs: [0, 120, 240], ys: [0, -50, 25]}
+";
+    let project = Project::from_yaml_str(input).expect("parse curves");
+    let curve = project.voice_parts[0].pitch_curve().expect("pitd curve");
+    assert_eq!(curve.to_points(), [(0, 0), (120, -50), (240, 25)]);
+
+    let reparsed =
+        Project::from_yaml_str(&project.to_yaml_string().expect("serialize")).expect("reparse");
+    assert_eq!(reparsed.voice_parts[0].pitch_curve(), Some(curve));
+
+    let truncated = ustx::Curve {
+        abbr: String::from("pitd"),
+        xs: vec![0, 10, 20],
+        ys: vec![1],
+    };
+    assert_eq!(truncated.to_points(), [(0, 1)]);
+    assert!(ustx::VoicePart::default().pitch_curve().is_none());
+}
+
+#[test]
+fn converts_between_project_and_yaml_value() {
+    let mut value: serde_yaml::Value = serde_yaml::from_str(sample_yaml()).expect("parse value");
+    value["name"] = serde_yaml::Value::from("Merged");
+    let project = Project::try_from(value).expect("project from value");
+    assert_eq!(project.name, "Merged");
+
+    let value = serde_yaml::Value::try_from(&project).expect("value from project");
+    assert_eq!(value["comment"].as_str(), Some("sample"));
+    assert_eq!(Project::try_from(value).expect("round trip"), project);
+    assert!(Project::try_from(serde_yaml::Value::from(3)).is_err());
+}
+
+#[test]
+fn swings_and_humanizes_note_positions() {
+    let mut part = Project::from_yaml_str(
+        "voice_parts:\n- notes:\n  - {position: 0}\n  - {position: 240}\n  - {position: 480}\n  - {position: 720}\n  - {position: 800}\n",
+    )
+    .expect("parse notes")
+    .voice_parts
+    .remove(0);
+    let original = part.clone();
+    part.apply_swing(240, 0.25);
+    let positions = part
+        .notes
+        .iter()
+        .map(|note| note.position)
+        .collect::<Vec<_>>();
+    assert_eq!(positions, [0, 300, 480, 780, 800]);
+
+    let mut extreme = original.clone();
+    extreme.notes[1].position = i32::MAX - 240;
+    extreme.apply_swing(1, 1e10);
+    assert_eq!(extreme.notes[1].position, i32::MAX - 239);
+    extreme.apply_swing(1, f32::NAN);
+    assert_eq!(extreme.notes[1].position, i32::MAX - 239);
+    extreme.notes[1].position = i32::MAX;
+    extreme.apply_swing(1, 1.0);
+    assert_eq!(extreme.notes[1].position, i32::MAX);
+
+    let mut first = original.clone();
+    let mut second = original.clone();
+    first.humanize(10, 42);
+    second.humanize(10, 42);
+    assert_eq!(first, second);
+    for (jittered, note) in first.notes.iter().zip(&original.notes) {
+        assert!((jittered.position - note.position).abs() <= 10);
+        assert!(jittered.position >= 0);
+    }
+    let mut other = original.clone();
+    other.humanize(10, 7);
+    assert_ne!(other, first);
+    other = original.clone();
+    other.humanize(0, 7);
+    assert_eq!(other, original);
+}
+
+#[test]
+fn repairs_parts_without_tracks() {
+    let mut project = Project::from_yaml_str(
+        "tracks: []\nvoice_parts:\n- {track_no: 0}\n- {track_no: 2}\nwave_parts:\n- {track_no: -1}\n",
+    )
+    .expect("parse parts");
+    assert_eq!(
+        project.validate(),
+        [
+            ValidationIssue::OrphanPart {
+                part_index: 0,
+                track_no: 0
+            },
+            ValidationIssue::OrphanPart {
+                part_index: 1,
+                track_no: 2
+            },
+            ValidationIssue::OrphanWavePart {
+                part_index: 0,
+                track_no: -1
+            },
+        ]
+    );
+    assert_eq!(project.ensure_tracks_for_parts(), 3);
+    assert_eq!(project.tracks.len(), 3);
+    assert_eq!(project.ensure_tracks_for_parts(), 0);
+    assert_eq!(project.validate().len(), 1);
+
+    project.voice_parts[0].track_no = i32::MAX;
+    assert_eq!(project.ensure_tracks_for_parts(), 0);
+    assert_eq!(project.tracks.len(), 3);
+    assert!(project.validate().contains(&ValidationIssue::OrphanPart {
+        part_index: 0,
+        track_no: i32::MAX
+    }));
+}
+
+#[test]
+fn repairs_duplicate_tempo_positions() {
+    let mut project = Project::from_yaml_str(
+        "tempos:\n- {position: 960, bpm: 90}\n- {position: 0, bpm: 120}\n- {position: 960, bpm: 100}\n\
+         time_signatures:\n- {bar_position: 0, beat_per_bar: 4, beat_unit: 4}\n\
+         - {bar_position: 0, beat_per_bar: 3, beat_unit: 4}\n",
+    )
+    .expect("parse tempo map");
+    assert_eq!(
+        project.validate(),
+        [
+            ValidationIssue::DuplicateTimeSignature(0),
+            ValidationIssue::DuplicateTempoPosition(960),
+        ]
+    );
+    assert_eq!(project.dedup_tempos(), 2);
+    assert!(project.validate().is_empty());
+    let tempos = project
+        .tempos
+        .iter()
+        .map(|tempo| (tempo.position, tempo.bpm))
+        .collect::<Vec<_>>();
+    assert_eq!(tempos, [(0, 120.0), (960, 100.0)]);
+    assert_eq!(project.time_signatures.len(), 1);
+    assert_eq!(project.time_signatures[0].beat_per_bar, 3);
+    assert_eq!(project.dedup_tempos(), 0);
+}
+
+#[test]
+fn removes_rest_notes() {
+    let mut part = ustx::VoicePart::default();
+    for lyric in ["a", "R", "+", "r", "", "+~", "Ra"] {
+        part.push_note_after(Note::builder().duration(120).lyric(lyric).build());
+    }
+    assert!(!part.notes[2].is_rest());
+    assert_eq!(part.remove_rests(), 3);
+    let lyrics = part
+        .notes
+        .iter()
+        .map(|note| note.lyric.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(lyrics, ["a", "+", "+~", "Ra"]);
+}
+
+#[test]
+fn reads_tone_names() {
+    let note: Note = serde_yaml::from_str("{tone: C4}").expect("named tone");
+    assert_eq!(note.tone, 60);
+    let note: Note = serde_yaml::from_str("{tone: 'A#3'}").expect("sharp tone");
+    assert_eq!(note.tone, 58);
+    let note: Note = serde_yaml::from_str("{tone: '62'}").expect("quoted number");
+    assert_eq!(note.tone, 62);
+    assert!(serde_yaml::from_str::<Note>("{tone: H2}").is_err());
+    let yaml = serde_yaml::to_string(&Note::builder().tone(60).build()).expect("serialize");
+    assert!(yaml.contains("tone: 60"));
+}
+
+#[test]
+fn reads_boolean_strings() {
+    let track: ustx::Track =
+        serde_yaml::from_str("{mute: 'True', solo: '0'}").expect("string booleans");
+    assert!(track.mute);
+    assert!(!track.solo);
+    let track: ustx::Track = serde_yaml::from_str("{mute: false, solo: 1}").expect("native");
+    assert!(!track.mute);
+    assert!(track.solo);
+    let pitch: ustx::Pitch = serde_yaml::from_str("{snap_first: 'false'}").expect("snap");
+    assert!(!pitch.snap_first);
+    assert!(serde_yaml::from_str::<ustx::Track>("{mute: 'yes'}").is_err());
+    let yaml = serde_yaml::to_string(&track).expect("serialize");
+    assert!(yaml.contains("solo: true"));
+}
+
+#[test]
+fn sorted_clone_ignores_element_order() {
+    let mut a = Project::from_yaml_str(sample_yaml()).expect("parse sample");
+    a.tempos = vec![
+        Tempo {
+            position: 0,
+            bpm: 120.0,
+        },
+        Tempo {
+            position: 960,
+            bpm: 90.0,
+        },
+    ];
+    let mut part = ustx::VoicePart::default();
+    part.push_note_after(Note::builder().duration(240).lyric("a").build());
+    part.push_note_after(Note::builder().duration(240).lyric("b").build());
+    a.voice_parts.push(part);
+    let mut b = a.clone();
+    b.tempos.reverse();
+    b.voice_parts[0].notes.reverse();
+    assert_ne!(a.content_hash(), b.content_hash());
+    assert_eq!(a.sorted_clone(), b.sorted_clone());
+    assert_eq!(
+        a.sorted_clone().content_hash(),
+        b.sorted_clone().content_hash()
+    );
+    assert_eq!(b.tempos[0].position, 960);
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn round_trips_gzip() {
+    let project = Project::from_yaml_str(sample_yaml()).expect("parse sample");
+    let mut compressed = Vec::new();
+    project.to_gzip_writer(&mut compressed).expect("compress");
+    assert_eq!(&compressed[..2], [0x1f, 0x8b]);
+    let restored = Project::from_gzip_reader(compressed.as_slice()).expect("decompress");
+    assert_eq!(restored, project);
+    let err = Project::from_gzip_reader(sample_yaml().as_bytes()).expect_err("not gzip");
+    assert!(matches!(err, ustx::Error::Io(_)));
+}
+
+#[test]
+fn resolves_note_phonemes() {
+    let base = ["k", "a", "N"].map(String::from);
+    let mut note = Note::builder().lyric("kan").build();
+    note.phoneme_overrides.push(PhonemeOverride {
+        index: 1,
+        phoneme: Some(String::from("aa")),
+        offset: Some(30),
+        preutter_delta: Some(5.0),
+        ..PhonemeOverride::default()
+    });
+    let all = note.resolved_phonemes(&base);
+    assert_eq!(all.len(), 3);
+    assert_eq!(all[1].phoneme, "aa");
+    assert_eq!(all[1].offset, 30);
+    assert_eq!(all[1].preutter.to_bits(), 5.0_f32.to_bits());
+    assert_eq!(all[2].phoneme, "N");
+
+    note.phoneme_indexes = vec![1, 7, -1];
+    let selected = note.resolved_phonemes(&base);
+    assert_eq!(selected.len(), 1);
+    assert_eq!(selected[0].phoneme, "aa");
+}
+
+#[test]
+fn orders_notes_by_position_then_tone() {
+    let low = Note::builder().position(480).tone(60).build();
+    let high = Note::builder().position(480).tone(64).build();
+    let early = Note::builder().position(0).tone(72).build();
+    assert_eq!(low.sort_key(), (480, 60));
+    assert!(early < low && low < high);
+    assert_eq!(
+        low.partial_cmp(&low.clone()),
+        Some(std::cmp::Ordering::Equal)
+    );
+    let other_lyric = Note::builder().position(480).tone(60).lyric("i").build();
+    assert_eq!(low.partial_cmp(&other_lyric), None);
+
+    let mut notes = vec![high.clone(), early.clone(), low.clone()];
+    notes.sort_by_key(Note::sort_key);
+    assert_eq!(notes, [early, low, high]);
+}
+
+#[test]
+fn maps_voice_colors() {
+    let track = ustx::Track {
+        voice_color_names: vec![String::new(), String::from("soft"), String::from("power")],
+        ..ustx::Track::default()
+    };
+    assert_eq!(track.color_index("power"), Some(2));
+    assert_eq!(track.color_index("whisper"), None);
+
+    let mut note = Note::builder().build();
+    assert_eq!(note.voice_color(&track), Some(""));
+    note.phoneme_expressions
+        .push(Expression::numerical("clr", 1.0));
+    assert_eq!(note.voice_color(&track), Some("soft"));
+    note.phoneme_expressions[0].value = 5.0;
+    assert_eq!(note.voice_color(&track), None);
+    note.phoneme_expressions[0].value = -1.0;
+    assert_eq!(note.voice_color(&track), None);
+}
+
+#[test]
+fn enforces_parse_limits() {
+    let yaml = "voice_parts:\n- notes: [{}, {}, {}]\n- {}\nwave_parts:\n- {}\n";
+    let limits = ustx::ParseLimits {
+        max_parts: 3,
+        max_notes: 3,
+        ..ustx::ParseLimits::default()
+    };
+    let project = Project::from_yaml_str_limited(yaml, limits).expect("within limits");
+    assert_eq!(project.voice_parts[0].notes.len(), 3);
+
+    let err = Project::from_yaml_str_limited(
+        yaml,
+        ustx::ParseLimits {
+            max_notes: 2,
+            ..limits
+        },
+    )
+    .expect_err("too many notes");
+    assert!(matches!(
+        err,
+        ustx::Error::LimitExceeded {
+            limit: "notes",
+            max: 2,
+            actual: 3
+        }
+    ));
+    let err = Project::from_yaml_str_limited(
+        yaml,
+        ustx::ParseLimits {
+            max_parts: 2,
+            ..limits
+        },
+    )
+    .expect_err("too many parts");
+    assert!(matches!(
+        err,
+        ustx::Error::LimitExceeded {
+            limit: "parts",
+            actual: 3,
+            ..
+        }
+    ));
+    let err = Project::from_yaml_str_limited(
+        yaml,
+        ustx::ParseLimits {
+            max_bytes: 8,
+            ..limits
+        },
+    )
+    .expect_err("too large");
+    assert!(matches!(
+        err,
+        ustx::Error::LimitExceeded { limit: "bytes", .. }
+    ));
+}
+
+#[test]
+fn renames_expression_references() {
+    let mut project = Project::default();
+    project.add_standard_expressions();
+    project.tracks[0]
+        .track_expressions
+        .push(Expression::numerical("gen", 10.0));
+    let mut part = ustx::VoicePart::default();
+    let mut note = Note::builder().build();
+    note.phoneme_expressions
+        .push(Expression::numerical("gen", 20.0));
+    note.phoneme_expressions
+        .push(Expression::numerical("vel", 90.0));
+    part.notes.push(note);
+    part.curves.push(ustx::Curve {
+        abbr: String::from("gen"),
+        ..ustx::Curve::default()
+    });
+    project.voice_parts.push(part);
+    project.exp_selectors = vec![String::from("dyn"), String::from("gen")];
+
+    assert!(project.rename_expression("gen", "vel").is_err());
+    assert!(project.rename_expression("nope", "xyz").is_err());
+    assert!(project.rename_expression("nope", "nope").is_err());
+    let before = project.clone();
+    assert_eq!(
+        project.rename_expression("gen", "gen").expect("identity"),
+        0
+    );
+    assert_eq!(project, before);
+    assert_eq!(
+        project.rename_expression("gen", "gender").expect("rename"),
+        4
+    );
+    assert!(!project.expressions.contains_key("gen"));
+    assert_eq!(project.expressions["gender"].abbr, "gender");
+    assert_eq!(project.tracks[0].track_expressions[0].abbr, "gender");
+    assert_eq!(project.voice_parts[0].curves[0].abbr, "gender");
+    assert_eq!(
+        project.voice_parts[0].notes[0].phoneme_expressions[1].abbr,
+        "vel"
+    );
+    assert_eq!(project.exp_selectors[1], "gender");
+}
+
+#[test]
+fn compares_projects_within_tolerance() {
+    let mut project = Project::from_yaml_str(sample_yaml()).expect("parse sample");
+    project.tempos = vec![Tempo {
+        position: 0,
+        bpm: 120.0,
+    }];
+    let mut drifted = project.clone();
+    drifted.bpm = 119.999_99;
+    drifted.tempos[0].bpm = 120.000_01;
+    assert_ne!(project, drifted);
+    assert!(project.approx_eq(&drifted, 1e-3));
+    assert!(!project.approx_eq(&drifted, 1e-9));
+    drifted.name.push('!');
+    assert!(!project.approx_eq(&drifted, 1.0));
+}
+
+#[test]
+fn splits_long_notes_into_ties() {
+    let mut part = ustx::VoicePart::default();
+    let mut long = Note::builder()
+        .position(480)
+        .duration(1000)
+        .tone(62)
+        .lyric("la")
+        .build();
+    long.vibrato = Vibrato::preset_light();
+    part.notes.push(long);
+    part.notes.push(
+        Note::builder()
+            .position(1480)
+            .duration(200)
+            .lyric("li")
+            .build(),
+    );
+    part.split_long_notes(480);
+    let chain = part
+        .notes
+        .iter()
+        .map(|note| (note.position, note.duration, note.tone, note.lyric.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        chain,
+        [
+            (480, 480, 62, "la"),
+            (960, 480, 62, "+"),
+            (1440, 40, 62, "+"),
+            (1480, 200, 0, "li"),
+        ]
+    );
+    assert_eq!(part.notes[0].vibrato, Vibrato::preset_light());
+    assert_eq!(part.notes[1].vibrato, Vibrato::none());
+}
+
+#[test]
+fn summarizes_pan_layout() {
+    let track = |pan: f64, mute: bool, solo: bool| ustx::Track {
+        pan,
+        mute,
+        solo,
+        ..ustx::Track::default()
+    };
+    let mut project = Project {
+        tracks: vec![
+            track(-0.5, false, false),
+            track(2.0, false, false),
+            track(0.0, true, false),
+        ],
+        ..Project::default()
+    };
+    assert_eq!(project.pan_layout(), [(0, -0.5), (1, 1.0)]);
+    assert!(!project.is_effectively_silent());
+
+    project.tracks[1].solo = true;
+    assert_eq!(project.pan_layout(), [(1, 1.0)]);
+    project.tracks[1].volume = ustx::Track::MIN_VOLUME;
+    assert!(project.is_effectively_silent());
+    project.tracks[1].mute = true;
+    assert!(project.pan_layout().is_empty());
+    assert!(project.is_effectively_silent());
+}
+
+#[test]
+fn resolves_solo_over_mute() {
+    let track = |mute: bool, solo: bool| ustx::Track {
+        mute,
+        solo,
+        ..ustx::Track::default()
+    };
+    let mut project = Project {
+        tracks: vec![track(false, false), track(true, false), track(false, false)],
+        ..Project::default()
+    };
+    assert_eq!(project.audible_tracks(), [0, 2]);
+    project.tracks[2].solo = true;
+    project.tracks[1].solo = true;
+    assert_eq!(project.audible_tracks(), [2]);
+    assert!(!project.tracks[0].is_audible(true));
+    assert!(project.tracks[0].is_audible(false));
+    assert!(!project.tracks[1].is_audible(true));
+}
+
+#[test]
+fn toggles_track_mix_controls() {
+    let mut project = Project {
+        tracks: vec![ustx::Track::default(); 3],
+        ..Project::default()
+    };
+    project.tracks[0].set_solo(true);
+    project.tracks[2].set_solo(true);
+    project.tracks[1].set_muted(true);
+    assert_eq!(project.audible_tracks(), [0, 2]);
+    assert_eq!(project.clear_all_solo(), 2);
+    assert_eq!(project.audible_tracks(), [0, 2]);
+    assert_eq!(project.clear_all_solo(), 0);
+
+    let track = &mut project.tracks[1];
+    track.volume = 6.0;
+    track.pan = -0.5;
+    track.set_solo(true);
+    track.reset_mix();
+    assert_eq!(*track, ustx::Track::default());
+}
+
+#[test]
+fn formats_summary_line() {
+    let mut project = Project::from_yaml_str(sample_yaml()).expect("parse sample");
+    project.ustx_version = Some("0.7".parse().expect("version"));
+    let mut part = ustx::VoicePart::default();
+    part.push_note_after(Note::builder().lyric("a").build());
+    project.voice_parts.push(part);
+    let tracks = project.tracks.len();
+    assert_eq!(
+        project.summary_line(),
+        format!("Demo — {tracks} tracks, 1 part, 1 note, 120 BPM, 4/4, ustx 0.7")
+    );
+    project.ustx_version = None;
+    assert!(project.summary_line().ends_with("4/4"));
+}
+
+#[test]
+fn fits_part_into_range_by_octaves() {
+    let mut part = ustx::VoicePart::default();
+    for tone in [84, 86, 88, 91, 72] {
+        part.push_note_after(Note::builder().tone(tone).build());
+    }
+    assert_eq!(part.fit_to_range(60, 80), -12);
+    let tones = part.notes.iter().map(|note| note.tone).collect::<Vec<_>>();
+    assert_eq!(tones, [72, 74, 76, 79, 60]);
+    assert_eq!(part.fit_to_range(60, 80), 0);
+    assert_eq!(part.fit_to_range(80, 60), 0);
+    assert_eq!(ustx::VoicePart::default().fit_to_range(0, 127), 0);
+
+    part.notes.truncate(2);
+    part.notes[0].tone = -20;
+    part.notes[1].tone = i32::MAX;
+    assert_eq!(part.fit_to_range(0, 127), 24);
+    let tones = part.notes.iter().map(|note| note.tone).collect::<Vec<_>>();
+    assert_eq!(tones, [4, i32::MAX]);
+}
+
+#[test]
+fn counts_lyric_frequencies() {
+    let mut project = Project::default();
+    let mut part = ustx::VoicePart::default();
+    for lyric in ["la", "+", "la", "R", "li", "+"] {
+        part.push_note_after(Note::builder().lyric(lyric).build());
+    }
+    project.voice_parts.push(part);
+    let all = project.lyric_frequencies();
+    assert_eq!(all["la"], 2);
+    assert_eq!(all["+"], 2);
+    assert_eq!(all["R"], 1);
+    let sung = project.lyric_frequencies_filtered(false);
+    assert_eq!(
+        sung.into_iter().collect::<Vec<_>>(),
+        [(String::from("la"), 2), (String::from("li"), 1)]
+    );
+    let unique = project.unique_lyrics();
+    assert_eq!(unique.len(), 4);
+    assert!(unique.contains("R"));
+}
+
+#[test]
+fn upgrades_project_without_version_through_every_step() {
+    let input = r#"name: Unversioned
+bpm: 100.0
+beat_per_bar: 3
+beat_unit: 4
+expressions:
+  acc: {name: accent, abbr: acc, min: 0, max: 200, default_value: 100}
+exp_selectors: [dyn]
+tempos: [{position: 480, bpm: 80.0}]
+voice_parts:
+- notes:
+  - {lyric: ...a, phoneme_expressions: [{abbr: acc, value: 50.0}]}
+"#;
+    let mut project = Project::from_yaml_str(input).expect("parse unversioned");
+    assert_eq!(project.ustx_version, None);
+    project.convert_to(CURRENT_VERSION).expect("upgrade");
+    assert_eq!(project.ustx_version, Some(CURRENT_VERSION));
+    // 0.4 renames `acc` to `atk`.
+    assert!(project.expressions.contains_key("atk"));
+    let note = &project.voice_parts[0].notes[0];
+    assert_eq!(note.phoneme_expressions[0].abbr, "atk");
+    // 0.5 rewrites the legacy connector.
+    assert_eq!(note.lyric, "+a");
+    // 0.6 seeds the tempo and time signature from the legacy fields.
+    assert_eq!(
+        project.tempos,
+        [Tempo {
+            position: 0,
+            bpm: 100.0
+        }]
+    );
+    assert_eq!(project.time_signatures[0].beat_per_bar, 3);
+    // 0.7 pads the expression selectors.
+    assert!(project.exp_selectors.len() > 1);
+
+    assert!(Project::from_yaml_str("ustx_version: banana").is_err());
+    let tagged = Project::default().with_version(ustx::Version::new(0, 5, 0));
+    assert_eq!(tagged.ustx_version, Some(ustx::Version::new(0, 5, 0)));
+}
+
+#[test]
+fn shifts_song_in_time() {
+    let mut project = Project {
+        tempos: vec![
+            Tempo {
+                position: 0,
+                bpm: 120.0,
+            },
+            Tempo {
+                position: 3840,
+                bpm: 90.0,
+            },
+        ],
+        time_signatures: vec![
+            TimeSignature {
+                bar_position: 0,
+                beat_per_bar: 4,
+                beat_unit: 4,
+            },
+            TimeSignature {
+                bar_position: 2,
+                beat_per_bar: 3,
+                beat_unit: 4,
+            },
+        ],
+        ..Project::default()
+    };
+    let mut part = ustx::VoicePart {
+        position: 480,
+        ..ustx::VoicePart::default()
+    };
+    part.notes.push(Note::builder().position(240).build());
+    project.voice_parts.push(part);
+
+    assert_eq!(project.shift_time(1920), 0);
+    assert_eq!(project.voice_parts[0].position, 2400);
+    assert_eq!(project.voice_parts[0].notes[0].position, 240);
+    assert_eq!(project.tempos[0].position, 0);
+    assert_eq!(project.tempos[1].position, 5760);
+    assert_eq!(project.time_signatures[1].bar_position, 3);
+
+    assert_eq!(project.shift_time(100), 0);
+    assert_eq!(project.time_signatures[1].bar_position, 3);
+
+    assert_eq!(project.shift_time(-3000), 1);
+    assert_eq!(project.voice_parts[0].position, 0);
+    assert_eq!(project.tempos[1].position, 2860);
+
+    assert_eq!(project.shift_time(-7680), 3);
+    let tempos = project
+        .tempos
+        .iter()
+        .map(|tempo| (tempo.position, tempo.bpm))
+        .collect::<Vec<_>>();
+    assert_eq!(tempos, [(0, 90.0)]);
+    assert_eq!(project.time_signatures.len(), 1);
+    assert_eq!(project.time_signatures[0].beat_per_bar, 3);
+    assert!(project.validate().is_empty());
+}
+
+#[test]
+fn converts_typed_time_units() {
+    let project = Project::default();
+    let ms = project.tick_to_ms(ustx::Ticks(960));
+    assert!((f64::from(ms) - 1000.0).abs() < 1e-9);
+    assert!((project.ms_to_tick(ms).0 - 960.0).abs() < 1e-9);
+    assert_eq!(i32::from(ustx::Ticks::from(480)), 480);
+    let ticks = project.ms_to_tick(ustx::Millis(1000.4));
+    assert_eq!(ticks.round(), ustx::Ticks(960));
+    assert_eq!(ustx::FractionalTicks(1e12).round(), ustx::Ticks(i32::MAX));
+    assert_eq!(
+        ustx::FractionalTicks::from(ustx::Ticks(5)),
+        ustx::FractionalTicks(5.0)
+    );
+}
+
+#[test]
+fn reports_expression_usage() {
+    let mut project = Project::default();
+    project.add_standard_expressions();
+    project.tracks[0]
+        .track_expressions
+        .push(Expression::numerical("vel", 90.0));
+    let mut part = ustx::VoicePart::default();
+    part.curves.push(ustx::Curve {
+        abbr: String::from("dyn"),
+        ..ustx::Curve::default()
+    });
+    let mut note = Note::builder().build();
+    note.phoneme_expressions
+        .push(Expression::numerical("vel", 80.0));
+    note.phoneme_expressions
+        .push(Expression::numerical("xyz", 1.0));
+    part.notes.push(note);
+    project.voice_parts.push(part);
+
+    let usage = project.expression_usage();
+    assert_eq!(usage["vel"], 2);
+    assert_eq!(usage["dyn"], 1);
+    assert_eq!(project.undeclared_used_expressions(), ["xyz"]);
+    let unused = project.unused_expressions();
+    assert!(unused.contains(&String::from("gen")));
+    assert!(!unused.contains(&String::from("vel")));
+    assert_eq!(unused.len(), ustx::STANDARD_EXPRESSIONS.len() - 2);
+}
+
+#[test]
+fn parses_arbitrary_bytes_without_panicking() {
+    let err = Project::from_yaml_bytes(b"name: \xff\xfe").expect_err("invalid utf-8");
+    assert!(matches!(err, ustx::Error::Encoding(_)));
+    let sample = sample_yaml().as_bytes();
+    assert!(Project::from_yaml_bytes(sample).is_ok());
+    for end in 0..sample.len() {
+        let _ = Project::from_yaml_bytes(&sample[..end]);
+    }
+    for garbage in [
+        &b"[[[[[[[[[["[..],
+        b"&a [*a, *a]",
+        b"\0\0\0",
+        b"voice_parts: 7",
+        b"---\n...",
+    ] {
+        let _ = Project::from_yaml_bytes(garbage);
+    }
+}
+
+#[test]
+fn names_project_key() {
+    let mut project = Project::default();
+    assert_eq!(project.key_name(), "C");
+    project.key = 18;
+    assert_eq!(project.key_name(), "F#");
+    project.key = -1;
+    assert_eq!(project.key_name(), "B");
+    project.set_key_by_name("Bb").expect("flat key");
+    assert_eq!(project.key, 10);
+    project.set_key_by_name("cb").expect("wrapping key");
+    assert_eq!(project.key, 11);
+    assert!(project.set_key_by_name("C4").is_err());
+    assert!(project.set_key_by_name("H").is_err());
+    assert_eq!(project.key, 11);
+    assert_eq!(ustx::pitch::parse_tone_name("Db-1", 4).ok(), Some(1));
+}
+
+#[cfg(feature = "audio")]
+#[test]
+fn refreshes_wave_part_duration() {
+    let dir = std::env::temp_dir().join(format!("ustx-duration-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).expect("create temp dir");
+    write_wav(&dir.join("take.wav"), 8000, &[0; 12_000]);
+    let mut project = Project::from_yaml_str("wave_parts:\n- {relative_path: take.wav}\n")
+        .expect("parse wave part");
+    let part = &mut project.wave_parts[0];
+    part.refresh_duration(&dir).expect("refresh duration");
+    assert!((part.file_duration_ms - 1500.0).abs() < 1e-9);
+
+    part.relative_path = String::from("missing.wav");
+    assert!(matches!(
+        part.refresh_duration(&dir),
+        Err(ustx::Error::Audio(_))
+    ));
+    assert!((part.file_duration_ms - 1500.0).abs() < 1e-9);
+    std::fs::remove_dir_all(&dir).expect("remove temp dir");
+}
+
+#[test]
+fn builds_sorted_timeline() {
+    use ustx::TimelineEvent;
+
+    let mut project = Project {
+        tempos: vec![
+            Tempo {
+                position: 0,
+                bpm: 120.0,
+            },
+            Tempo {
+                position: 1920,
+                bpm: 90.0,
+            },
+        ],
+        time_signatures: vec![
+            TimeSignature {
+                bar_position: 0,
+                beat_per_bar: 4,
+                beat_unit: 4,
+            },
+            TimeSignature {
+                bar_position: 1,
+                beat_per_bar: 3,
+                beat_unit: 4,
+            },
+            TimeSignature {
+                bar_position: 3,
+                beat_per_bar: 4,
+                beat_unit: 4,
+            },
+        ],
+        ..Project::default()
+    };
+    assert_eq!(project.bar_to_tick(3), 1920 + 2 * 1440);
+    let mut part = ustx::VoicePart {
+        position: 1440,
+        ..ustx::VoicePart::default()
+    };
+    part.notes
+        .push(Note::builder().duration(480).tone(60).build());
+    part.notes
+        .push(Note::builder().position(480).duration(480).tone(60).build());
+    project.voice_parts.push(part);
+
+    let events = project.timeline_events();
+    let ticks = events.iter().map(TimelineEvent::tick).collect::<Vec<_>>();
+    assert!(ticks.is_sorted());
+    assert_eq!(events.len(), 3 + 2 + 4);
+    let at_1920 = events
+        .iter()
+        .filter(|event| event.tick() == 1920)
+        .collect::<Vec<_>>();
+    assert!(matches!(
+        at_1920[0],
+        TimelineEvent::TimeSignatureChange { bar: 1, .. }
+    ));
+    assert!(matches!(at_1920[1], TimelineEvent::TempoChange { bpm, .. } if *bpm == 90.0));
+    assert!(matches!(
+        at_1920[2],
+        TimelineEvent::NoteOff { note_index: 0, .. }
+    ));
+    assert!(matches!(
+        at_1920[3],
+        TimelineEvent::NoteOn { note_index: 1, .. }
+    ));
+    assert!(matches!(
+        events.last(),
+        Some(TimelineEvent::TimeSignatureChange {
+            tick: 4800,
+            bar: 3,
+            ..
+        })
+    ));
+
+    project.time_signatures.push(TimeSignature {
+        bar_position: 3_000_000,
+        beat_per_bar: 4,
+        beat_unit: 4,
+    });
+    project.voice_parts[0].position = i32::MAX - 100;
+    assert_eq!(project.bar_to_tick(3_000_000), i32::MAX);
+    let ticks = project
+        .timeline_events()
+        .iter()
+        .map(TimelineEvent::tick)
+        .collect::<Vec<_>>();
+    assert_eq!(ticks.last(), Some(&i32::MAX));
+    let huge = TimeSignature {
+        bar_position: 0,
+        beat_per_bar: i32::MAX,
+        beat_unit: 1,
+    };
+    assert_eq!(huge.ticks_per_bar(480), i32::MAX);
+}
+
+#[cfg(feature = "midi")]
+#[test]
+fn exports_standard_midi_file() {
+    let mut project = Project {
+        tempos: vec![Tempo {
+            position: 0,
+            bpm: 120.0,
+        }],
+        ..Project::default()
+    };
+    let mut part = ustx::VoicePart::default();
+    part.push_note_after(Note::builder().duration(480).tone(60).lyric("la").build());
+    project.voice_parts.push(part);
+
+    let bytes = project.to_midi().expect("export midi");
+    assert_eq!(&bytes[..8], b"MThd\0\0\0\x06");
+    assert_eq!(&bytes[8..14], [0, 1, 0, 2, 0x01, 0xE0]);
+    let contains = |needle: &[u8]| bytes.windows(needle.len()).any(|window| window == needle);
+    assert!(contains(&[0xFF, 0x51, 3, 0x07, 0xA1, 0x20]));
+    assert!(contains(&[0xFF, 0x58, 4, 4, 2, 24, 8]));
+    assert!(contains(&[0, 0xFF, 0x05, 2, b'l', b'a', 0, 0x90, 60, 100]));
+    assert!(contains(&[0x83, 0x60, 0x80, 60, 0, 0, 0xFF, 0x2F, 0]));
+    assert_eq!(
+        bytes.windows(4).filter(|window| window == b"MTrk").count(),
+        2
+    );
+
+    project.voice_parts[0].notes[0].tone = 200;
+    assert!(matches!(
+        project.to_midi(),
+        Err(ustx::Error::InvalidValue { .. })
+    ));
+    project.voice_parts[0].notes[0].tone = 60;
+    project.time_signatures[0].beat_unit = 3;
+    assert!(project.to_midi().is_err());
+    project.time_signatures[0].beat_unit = 4;
+
+    project.voice_parts[0].notes[0].duration = 0;
+    let bytes = project.to_midi().expect("export midi");
+    assert!(!bytes.windows(2).any(|window| window == [0x90, 60]));
+    assert!(!bytes.windows(2).any(|window| window == [0x80, 60]));
+
+    project.voice_parts[0].notes[0].duration = 480;
+    project.voice_parts[0].position = 0x0FFF_FFFF;
+    let bytes = project.to_midi().expect("export midi");
+    assert!(
+        bytes
+            .windows(5)
+            .any(|window| window == [0xFF, 0xFF, 0xFF, 0x7F, 0xFF])
+    );
+    project.voice_parts[0].position = 0x1000_0000;
+    assert!(matches!(
+        project.to_midi(),
+        Err(ustx::Error::InvalidValue {
+            expected: "MIDI delta time",
+            ..
+        })
+    ));
+}
+
+#[test]
+fn finds_and_edits_notes_by_location() {
+    let mut project = Project::default();
+    for lyrics in [["a", "R"], ["R", "i"]] {
+        let mut part = ustx::VoicePart::default();
+        for lyric in lyrics {
+            part.push_note_after(Note::builder().lyric(lyric).build());
+        }
+        project.voice_parts.push(part);
+    }
+    let rests = project.find_notes(Note::is_rest);
+    assert_eq!(
+        rests,
+        [
+            ustx::NoteLocation {
+                part_index: 0,
+                note_index: 1
+            },
+            ustx::NoteLocation {
+                part_index: 1,
+                note_index: 0
+            },
+        ]
+    );
+    for &location in &rests {
+        project.note_at_mut(location).expect("found note").lyric = String::from("-");
+    }
+    assert_eq!(
+        project.note_at(rests[1]).map(|note| note.lyric.as_str()),
+        Some("-")
+    );
+    assert!(project.find_notes(Note::is_rest).is_empty());
+    assert!(
+        project
+            .note_at(ustx::NoteLocation {
+                part_index: 2,
+                note_index: 0
+            })
+            .is_none()
+    );
+}
+
+#[test]
+fn looks_up_expression_options() {
+    let eng = ExpressionDescriptor::standard("eng").expect("standard eng");
+    assert_eq!(eng.option_name(1.2), Some("worldline"));
+    assert_eq!(eng.option_name(0.0), Some(""));
+    assert_eq!(eng.option_name(2.0), None);
+    assert_eq!(eng.option_name(-1.0), None);
+    assert_eq!(eng.option_name(f32::NAN), None);
+    assert_eq!(eng.option_index("worldline"), Some(1));
+    assert_eq!(eng.option_index("classic"), None);
+    assert!(eng.validate().is_ok());
+
+    for abbr in ustx::STANDARD_EXPRESSIONS {
+        let descriptor = ExpressionDescriptor::standard(abbr).expect("standard descriptor");
+        assert!(descriptor.validate().is_ok(), "{abbr}");
+    }
+    let clr = ExpressionDescriptor::standard("clr").expect("standard clr");
+    assert_eq!(clr.options, [""]);
+    let no_options = ExpressionDescriptor {
+        options: Vec::new(),
+        ..clr
+    };
+    assert!(matches!(
+        no_options.validate(),
+        Err(ustx::Error::InvalidExpression(_))
+    ));
+    let mut track = ustx::Track {
+        voice_color_names: vec![String::from("soft"), String::from("power")],
+        ..ustx::Track::default()
+    };
+    let colors = track.voice_color_descriptor();
+    assert_eq!(colors.option_name(1.0), Some("power"));
+    assert_eq!(colors.max, 1.0);
+    assert!(colors.validate().is_ok());
+    track.voice_color_names.clear();
+    assert!(track.voice_color_descriptor().validate().is_err());
+    let inverted = ExpressionDescriptor {
+        min: 10.0,
+        max: 0.0,
+        ..ExpressionDescriptor::standard("vel").expect("standard vel")
+    };
+    assert!(inverted.validate().is_err());
+}